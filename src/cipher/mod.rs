@@ -1,12 +1,11 @@
 use aes_gcm::{aead::Aead, Aes256Gcm, Key, KeyInit, Nonce};
+use base64::{engine::general_purpose::STANDARD, Engine};
 use rand::Rng;
 use sha2::{Digest, Sha256};
 use serde::{Deserialize, Serialize};
-use std::fs::{self, File};
-use std::io::{self, BufRead, Read};
-use std::path::Path;
+use std::fs;
 
-const PASSPHRASE: &str = "yeeey";
+const NONCE_LEN: usize = 12;
 
 pub enum Error {
     
@@ -25,7 +24,7 @@ pub fn generate_fixed_key() -> Result<Key<Aes256Gcm>, &'static str> {
         let result = hasher.finalize();
         let key_bytes: [u8; 32] = result.as_slice().try_into().expect("SHA-256 must produce 32 bytes");
         
-        Ok(Key::<Aes256Gcm>::from_slice(&key_bytes).clone())
+        Ok(*Key::<Aes256Gcm>::from_slice(&key_bytes))
     } else {
         println!("err key.txt");
         Err("err")
@@ -36,7 +35,7 @@ pub fn encrypt_message(key: &Key<Aes256Gcm>, message: &str) -> (Vec<u8>, Vec<u8>
     let cipher = Aes256Gcm::new(key);
     
     // Generate a random 12-byte nonce (IV)
-    let binding = rand::thread_rng().gen::<[u8; 12]>();
+    let binding = rand::thread_rng().gen::<[u8; NONCE_LEN]>();
     let nonce = Nonce::from_slice(&binding);
     
     // Encrypt the message
@@ -56,4 +55,21 @@ pub fn decrypt_message(key: &Key<Aes256Gcm>, nonce: &[u8], ciphertext: &[u8]) ->
     } else {
         Err("err")
     }
-}
\ No newline at end of file
+}
+
+/// Encrypts `message` for storage on disk as one base64 string of nonce and ciphertext.
+pub fn seal(message: &str) -> Result<String, &'static str> {
+    let key = generate_fixed_key()?;
+    let (nonce, cipher_text) = encrypt_message(&key, message);
+    Ok(STANDARD.encode([nonce, cipher_text].concat()))
+}
+
+pub fn unseal(sealed: &str) -> Result<String, &'static str> {
+    let key = generate_fixed_key()?;
+    let bytes = STANDARD.decode(sealed).map_err(|_| "err base64")?;
+    if bytes.len() < NONCE_LEN {
+        return Err("err sealed too short");
+    }
+    let (nonce, cipher_text) = bytes.split_at(NONCE_LEN);
+    decrypt_message(&key, nonce, cipher_text)
+}
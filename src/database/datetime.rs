@@ -0,0 +1,71 @@
+//! Serde glue for the server's `DATETIME` columns, which arrive as
+//! `"%Y-%m-%d %H:%M:%S"` strings (see `table/query/preoperative.txt`).
+use chrono::NaiveDateTime;
+use serde::{de::Error, Deserialize, Deserializer, Serializer};
+
+pub const FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+pub fn parse(input: &str) -> Result<NaiveDateTime, chrono::ParseError> {
+    NaiveDateTime::parse_from_str(input, FORMAT)
+        .or_else(|_| NaiveDateTime::parse_from_str(input, "%Y-%m-%dT%H:%M:%S"))
+}
+
+pub fn serialize<S: Serializer>(value: &Option<NaiveDateTime>, serializer: S) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(datetime) => serializer.serialize_str(&datetime.format(FORMAT).to_string()),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<NaiveDateTime>, D::Error> {
+    match Option::<String>::deserialize(deserializer)? {
+        Some(input) => parse(&input)
+            .map(Some)
+            .map_err(|err| D::Error::custom(format!("malformed datetime {:?}: {}", input, err))),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{NaiveDate, NaiveDateTime};
+    use serde_json::json;
+
+    use crate::database::table::Operation;
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, 1).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn server_and_iso_formats_are_read() {
+        let op: Operation = serde_json::from_value(json!({
+            "id": 1,
+            "start_time": "2024-05-01 09:30:00",
+            "end_time": "2024-05-01T11:00:00",
+        })).unwrap();
+        assert_eq!(op.start_time, Some(at(9, 30)));
+        assert_eq!(op.end_time, Some(at(11, 0)));
+    }
+
+    #[test]
+    fn null_and_missing_times_are_none() {
+        let op: Operation = serde_json::from_value(json!({ "id": 1, "start_time": null })).unwrap();
+        assert_eq!(op.start_time, None);
+        assert_eq!(op.end_time, None);
+    }
+
+    #[test]
+    fn malformed_times_are_an_error() {
+        let err = serde_json::from_value::<Operation>(json!({ "id": 1, "start_time": "tomorrow" })).unwrap_err();
+        assert!(err.to_string().contains("malformed datetime \"tomorrow\""));
+    }
+
+    #[test]
+    fn times_are_written_back_in_the_server_format() {
+        let op = Operation { id: Some(1), start_time: Some(at(9, 30)), ..Operation::default() };
+        let value = serde_json::to_value(&op).unwrap();
+        assert_eq!(value["start_time"], json!("2024-05-01 09:30:00"));
+        assert_eq!(value["end_time"], json!(null));
+    }
+}
@@ -0,0 +1,12 @@
+//! Reads a field as `None` instead of rejecting the whole row when a newer
+//! server sends a value this build does not know, such as a new enum variant.
+use serde::{de::DeserializeOwned, Deserialize, Deserializer};
+
+pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: DeserializeOwned,
+{
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(value.and_then(|value| serde_json::from_value(value).ok()))
+}
@@ -1,2 +1,3 @@
+pub(crate) mod datetime;
+pub(crate) mod lenient;
 pub(crate) mod table;
-
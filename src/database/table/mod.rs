@@ -1,6 +1,9 @@
 use std::fmt;
+use chrono::NaiveDateTime;
 use serde::{Serialize, Deserialize};
 
+use crate::{table::{store::ForeignKey, Entity}, TableTarget};
+
 #[derive(Clone, Debug, PartialEq, PartialOrd, Deserialize, Serialize)]
 pub enum EquipmentStatus {
     Ready,
@@ -63,7 +66,8 @@ pub enum AlertNotificationStatus {
     Resolved
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Equipment {
     pub id: Option<i32>,
     pub name: Option<String>,
@@ -72,7 +76,8 @@ pub struct Equipment {
     pub brand: Option<String>
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 #[allow(non_snake_case)]
 pub struct Room {
     pub id: Option<i32>,
@@ -80,49 +85,65 @@ pub struct Room {
     pub alias_code: Option<String>,
     pub patient_maximum_occupancy: Option<i32>,
     pub staff_maximum_occupancy: Option<i32>,
+    #[serde(deserialize_with = "super::lenient::deserialize")]
     pub purpose: Option<RoomPurpose>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Tool {
     pub id: Option<i32>,
     pub info_id: Option<i32>,
+    #[serde(deserialize_with = "super::lenient::deserialize")]
     pub status: Option<EquipmentStatus>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Staff {
     pub id: Option<i32>,
     pub first_name: Option<String>,
     pub last_name: Option<String>,
     pub email: Option<String>,
     pub phone: Option<String>,
+    #[serde(deserialize_with = "super::lenient::deserialize")]
     pub role: Option<StaffRole>,
 }
+impl Staff {
+    pub fn full_name(&self) -> String {
+        full_name(&self.first_name, &self.last_name)
+    }
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ToolReservation {
     pub id: Option<i32>,
-    pub start_time: Option<String>, // Use chrono::NaiveDateTime for datetime
-    pub end_time: Option<String>,   // Use chrono::NaiveDateTime for datetime
+    #[serde(with = "super::datetime")]
+    pub start_time: Option<NaiveDateTime>,
+    #[serde(with = "super::datetime")]
+    pub end_time: Option<NaiveDateTime>,
     pub staff_incharge: Option<i32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ToolDesignatedRoom {
     pub id: Option<i32>,
     pub room_id: Option<i32>,
     pub tool_id: Option<i32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ToolInspector {
     pub id: Option<i32>,
     pub staff_id: Option<i32>,
     pub tool_id: Option<i32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Patient {
     pub id: Option<i32>,
     pub first_name: Option<String>,
@@ -130,40 +151,62 @@ pub struct Patient {
     pub email: Option<String>,
     pub phone: Option<String>,
 }
+impl Patient {
+    pub fn full_name(&self) -> String {
+        full_name(&self.first_name, &self.last_name)
+    }
+}
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// "First Last", with "N/A" standing in for a missing part.
+fn full_name(first_name: &Option<String>, last_name: &Option<String>) -> String {
+    format!(
+        "{} {}",
+        first_name.as_deref().unwrap_or("N/A"),
+        last_name.as_deref().unwrap_or("N/A")
+    )
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Operation {
     pub id: Option<i32>,
     pub label: Option<String>,
+    #[serde(deserialize_with = "super::lenient::deserialize")]
     pub status: Option<OperationStatus>,
     pub patient_id: Option<i32>,
     pub room_id: Option<i32>,
-    pub start_time: Option<String>, // Use chrono::NaiveDateTime for datetime
-    pub end_time: Option<String>,   // Use chrono::NaiveDateTime for datetime
+    #[serde(with = "super::datetime")]
+    pub start_time: Option<NaiveDateTime>,
+    #[serde(with = "super::datetime")]
+    pub end_time: Option<NaiveDateTime>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PatientWardRoom {
     pub id: Option<i32>,
     pub patient_id: Option<i32>,
     pub room_id: Option<i32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PatientWardAssistant {
     pub id: Option<i32>,
     pub staff_id: Option<i32>,
     pub patient_ward_room_id: Option<i32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct OperationStaff {
     pub id: Option<i32>,
     pub operation_id: Option<i32>,
     pub staff_id: Option<i32>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct OperationTool {
     pub id: Option<i32>,
     pub operation_id: Option<i32>,
@@ -171,15 +214,19 @@ pub struct OperationTool {
     pub on_site: Option<i8>, // Use Option<bool> for tinyint (0/1)
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Alert {
     pub id: Option<i32>,
+    #[serde(deserialize_with = "super::lenient::deserialize")]
     pub category: Option<AlertCategory>,
     pub operation_id: Option<i32>,
     pub submitted_staff_id: Option<i32>,
+    #[serde(deserialize_with = "super::lenient::deserialize")]
     pub notification_status: Option<AlertNotificationStatus>
 }
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct Frontdesk {
     pub id: Option<i32>,
     pub name: Option<String>,
@@ -187,15 +234,55 @@ pub struct Frontdesk {
     pub address_label: Option<String>,
     pub session_token: Option<String>
 }
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AlertFrontdesk {
     pub id: Option<i32>,
     pub frontdesk_id: Option<i32>,
     pub alert_id: Option<i32>,
 }
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct AlertStaff {
     pub id: Option<i32>,
     pub staff_id: Option<i32>,
     pub alert_id: Option<i32>,
-}
\ No newline at end of file
+}
+
+macro_rules! entity {
+    ($($row:ident => $name:literal $({ $($column:ident => $target:ident),* $(,)? })?),* $(,)?) => {
+        $(
+            impl Entity for $row {
+                const TARGET: TableTarget = TableTarget::$row;
+                const NAME: &'static str = $name;
+                fn id(&self) -> Option<i32> {
+                    self.id
+                }
+                fn foreign_keys(&self) -> Vec<ForeignKey> {
+                    vec![$($(
+                        ForeignKey { column: stringify!($column), target: TableTarget::$target, id: self.$column },
+                    )*)?]
+                }
+            }
+        )*
+    };
+}
+entity! {
+    Equipment => "equipment",
+    Room => "room",
+    Tool => "tool" { info_id => Equipment },
+    Staff => "staff",
+    ToolReservation => "tool_reservation" { staff_incharge => Staff },
+    ToolDesignatedRoom => "tool_designated_room" { room_id => Room, tool_id => Tool },
+    ToolInspector => "tool_inspector" { staff_id => Staff, tool_id => Tool },
+    Patient => "patient",
+    Operation => "operation" { patient_id => Patient, room_id => Room },
+    PatientWardRoom => "patient_ward_room" { patient_id => Patient, room_id => Room },
+    PatientWardAssistant => "patient_ward_assistant" { staff_id => Staff, patient_ward_room_id => PatientWardRoom },
+    OperationStaff => "operation_staff" { operation_id => Operation, staff_id => Staff },
+    OperationTool => "operation_tool" { operation_id => Operation, tool_id => Tool },
+    Alert => "alert" { operation_id => Operation, submitted_staff_id => Staff },
+    Frontdesk => "frontdesk",
+    AlertFrontdesk => "alert_frontdesk" { frontdesk_id => Frontdesk, alert_id => Alert },
+    AlertStaff => "alert_staff" { staff_id => Staff, alert_id => Alert },
+}
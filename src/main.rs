@@ -1,9 +1,11 @@
 mod table;
 mod database;
-use database::table::OperationStatus;
+use database::table::Alert;
 use table::{
-    query_return::{self, WindowTable::{self, *}}, BuildTable, Query, TableData::{self}
+    conflict::{room_conflicts, staff_conflicts}, query_return::{TableTree, WindowTable}, diff::SnapshotDiff, filter::Filter, pending::PendingUpdates, snapshot::STORAGE_KEY, BuildTable, LoadReport, StoredSnapshot, TableData::{self}
 };
+#[cfg(feature = "sql-mirror")]
+use table::mirror::SqlMirror;
 mod window;
 mod ws;
 use ws::receive::*;
@@ -13,9 +15,8 @@ use cipher::{decrypt_message, generate_fixed_key, EncryptedText};
 
 use chrono::{DateTime, Datelike, NaiveDateTime, Timelike, Utc};
 use window::{*};
-use eframe::{egui, App, Frame};
-use egui::{mutex::Mutex, Color32, Label, RichText, Sense};
-use egui_extras::{TableBuilder, Column};
+use eframe::{egui, App};
+use egui::Color32;
 use ewebsock::{self, WsReceiver, WsSender};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
@@ -29,17 +30,29 @@ struct SendMessage {
 
 struct FrontdeskApp {
     data: Option<TableData>,
-    rx: tokio::sync::mpsc::Receiver<String>,
-    tx: tokio::sync::mpsc::Sender<String>,
     sender: WsSender,
     receiver: WsReceiver,
     central_panel_window_show: CentralWindow,
+    load_report: Option<LoadReport>,
+    snapshot_error: Option<String>,
+    /// Set while showing a snapshot restored from disk instead of live data.
+    stale_since: Option<DateTime<Utc>>,
+    #[cfg(feature = "sql-mirror")]
+    mirror: Option<SqlMirror>,
+    /// Changes brought by each re-initialize, oldest first.
+    snapshot_diffs: Vec<SnapshotDiff>,
+    /// Updates applied locally and waiting for the server to accept them.
+    pending: PendingUpdates,
     //central_window: OperationWindow,
 }
 
-fn format_date(input: &str) -> String {
-    let naive_datetime = NaiveDateTime::parse_from_str(input, "%Y-%m-%d %H:%M:%S")
-        .expect("Failed to parse date");
+/// How many re-initialize diffs the snapshot changes window keeps.
+const SNAPSHOT_DIFF_HISTORY: usize = 10;
+
+fn format_date(input: Option<NaiveDateTime>) -> String {
+    let Some(naive_datetime) = input else {
+        return "N/A".to_string();
+    };
 
     let month = naive_datetime.month();
     let day = naive_datetime.day();
@@ -75,53 +88,255 @@ fn format_date(input: &str) -> String {
     format!("{} {}, {} {}", month_str, day, year, time_str)
 }
 
-fn date_code(start: &str, end: &str) -> Color32 {
-    let current_time_utc8 = Utc::now() + chrono::Duration::hours(8);
+fn format_age(age: chrono::Duration) -> String {
+    let minutes = age.num_minutes().max(0);
+    if minutes < 60 {
+        format!("{} min", minutes)
+    } else if minutes < 60 * 24 {
+        format!("{} h {} min", minutes / 60, minutes % 60)
+    } else {
+        format!("{} d {} h", minutes / (60 * 24), minutes / 60 % 24)
+    }
+}
+
+/// Wall-clock time in the server's timezone (UTC+8), which is how every
+/// operation time is stored.
+fn local_now() -> NaiveDateTime {
+    (Utc::now() + chrono::Duration::hours(8)).naive_utc()
+}
 
-    let start_time = NaiveDateTime::parse_from_str(start, "%Y-%m-%d %H:%M:%S").unwrap();
-    let end_time = NaiveDateTime::parse_from_str(end, "%Y-%m-%d %H:%M:%S").unwrap();
+fn date_code(start: Option<NaiveDateTime>, end: Option<NaiveDateTime>) -> Color32 {
+    let current_time_utc8 = local_now();
 
-    if current_time_utc8 < start_time.and_utc() {
+    let (Some(start_time), Some(end_time)) = (start, end) else {
+        return Color32::GRAY;
+    };
+
+    if current_time_utc8 < start_time {
         Color32::from_rgb(246, 140, 46)
-    } else if current_time_utc8 >= start_time.and_utc() && current_time_utc8 <= end_time.and_utc() {
+    } else if current_time_utc8 >= start_time && current_time_utc8 <= end_time {
         Color32::from_rgb(0, 140, 26)
     } else {
         Color32::from_rgb(255, 46, 32)
     }
 }
+/// Opens the server socket and asks for the initial snapshot. Every incoming
+/// event wakes the UI so new data is drawn without waiting for input.
+fn connect(ctx: &egui::Context) -> (WsSender, WsReceiver) {
+    let options = ewebsock::Options::default();
+    let ctx = ctx.clone();
+    let (mut sender, receiver) = ewebsock::connect_with_wakeup("ws://127.0.0.15:8080", options, move || ctx.request_repaint()).unwrap();
+
+    let request_json = serde_json::to_string(&SendMessage {
+        level: "Frontdesk".to_string(),
+        method: "Initial".to_string(),
+        data: Some(json!({"content": "Hello from button('Send Message')!"})),
+    }).unwrap();
+    sender.send(ewebsock::WsMessage::Text(request_json));
+
+    (sender, receiver)
+}
 impl FrontdeskApp {
     fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        let (tx, rx) = tokio::sync::mpsc::channel(32);
-        
-        let options = ewebsock::Options::default();
-        let (mut sender, receiver) = ewebsock::connect("ws://127.0.0.15:8080", options).unwrap();
+        let (sender, receiver) = connect(&cc.egui_ctx);
 
-        let request_json = serde_json::to_string(&SendMessage {
-            level: "Frontdesk".to_string(),
-            method: "Initial".to_string(),
-            data: Some(json!({"content": "Hello from button('Send Message')!"})),
-        }).unwrap();
-        sender.send(ewebsock::WsMessage::Text(request_json));
+        let stored_snapshot = cc.storage
+            .and_then(|storage| storage.get_string(STORAGE_KEY))
+            .and_then(|sealed| match StoredSnapshot::unseal(&sealed) {
+                Ok(stored_snapshot) => Some(stored_snapshot),
+                Err(err) => {
+                    println!("err restoring snapshot: {}", err);
+                    None
+                },
+            });
+        let stale_since = stored_snapshot.as_ref().map(StoredSnapshot::saved_at);
 
         FrontdeskApp {
-            data: None,
-            rx,
-            tx,
+            data: stored_snapshot.map(|stored_snapshot| stored_snapshot.data),
             sender,
             receiver,
             central_panel_window_show: CentralWindow::default(),
+            load_report: None,
+            snapshot_error: None,
+            stale_since,
+            #[cfg(feature = "sql-mirror")]
+            mirror: None,
+            snapshot_diffs: Vec::new(),
+            pending: PendingUpdates::default(),
         }
     }
     fn toggle_window(&mut self, central_window: CentralWindowEnum) {
-        match central_window {
-            CentralWindowEnum::InProgress => self.central_panel_window_show.in_progress.show = !self.central_panel_window_show.in_progress.show,
-            CentralWindowEnum::PreOperative => self.central_panel_window_show.pre_operative.show = !self.central_panel_window_show.pre_operative.show,
-
+        let show = self.central_panel_window_show.show_mut(central_window);
+        *show = !*show;
+    }
+    /// Shows the tools of `op_id` on the Pre-Operative board.
+    fn open_operation(&mut self, op_id: i32) {
+        let Some(data) = &mut self.data else {
+            return;
+        };
+        let window = &mut self.central_panel_window_show;
+        *window.show_mut(CentralWindowEnum::PreOperative) = true;
+        if !window.has_tree(CentralWindowEnum::PreOperative) {
+            window.initial_tree(CentralWindowEnum::PreOperative, TableTree::new(data, WindowTable::PreOperativeDefault(None), None));
         }
+        while !window.is_root_state(CentralWindowEnum::PreOperative) {
+            window.remove_last(CentralWindowEnum::PreOperative);
+        }
+        window.push_last(CentralWindowEnum::PreOperative, TableTree::new(data, WindowTable::PreOperativeToolReady(None), Some(op_id)));
+    }
+    fn send(&mut self, method: &str, data: Option<serde_json::Value>) {
+        let request_json = serde_json::to_string(&SendMessage {
+            level: "Frontdesk".to_string(),
+            method: method.to_string(),
+            data,
+        }).unwrap();
+        self.sender.send(ewebsock::WsMessage::Text(request_json));
+    }
+    /// Checks a request against the current rows, applies it optimistically
+    /// and sends it. Anything that cannot go through ends up as a toast.
+    fn handle_request(&mut self, request: DeskRequest) {
+        let Some(data) = &self.data else {
+            return;
+        };
+        let toasts = &mut self.central_panel_window_show.toasts;
+        let label = |id: i32| data.operation.read().get(id)
+            .and_then(|op| op.label.clone())
+            .unwrap_or_else(|| format!("operation #{}", id));
+        let sent = match request {
+            DeskRequest::Reschedule { op_id, start_time, end_time, room_id } => {
+                let Some(mut row) = data.operation.read().get(op_id).cloned() else {
+                    return;
+                };
+                let now = local_now();
+                let room_id = room_id.or(row.room_id);
+                if let Some(room_id) = room_id {
+                    let booked: Vec<String> = room_conflicts(data, op_id, room_id, start_time, end_time, now).into_iter().map(label).collect();
+                    if !booked.is_empty() {
+                        toasts.push(format!("⚠ {} not moved, the room is booked by {}", label(op_id), booked.join(", ")));
+                        return;
+                    }
+                }
+                let busy: Vec<String> = staff_conflicts(data, op_id, start_time, end_time, now).into_iter()
+                    .map(|(staff_id, other_id)| {
+                        let name = data.staff.read().get(staff_id)
+                            .map(|s| s.full_name())
+                            .unwrap_or_else(|| format!("staff #{}", staff_id));
+                        format!("{} ({})", name, label(other_id))
+                    })
+                    .collect();
+                if !busy.is_empty() {
+                    toasts.push(format!("⚠ {} not moved, staff busy: {}", label(op_id), busy.join(", ")));
+                    return;
+                }
+                row.start_time = Some(start_time);
+                row.end_time = Some(end_time);
+                row.room_id = room_id;
+                self.pending.apply(&data.operation, op_id, row, format!("moving {}", label(op_id)))
+                    .map(|raw_string| ("Update", TableTarget::Operation, raw_string))
+            },
+            DeskRequest::SetOnSite { op_tool_id, on_site } => {
+                let Some(mut row) = data.operation_tool.read().get(op_tool_id).cloned() else {
+                    return;
+                };
+                let tool_name = row.tool_id.and_then(|id| data.tool.read().get(id).and_then(|tool| tool.info_id))
+                    .and_then(|id| data.equipment.read().get(id).and_then(|equipment| equipment.name.clone()))
+                    .unwrap_or_else(|| format!("tool #{}", row.tool_id.unwrap_or_default()));
+                let description = format!(
+                    "marking {} {} for {}",
+                    tool_name,
+                    if on_site { "on site" } else { "not on site" },
+                    row.operation_id.map_or_else(|| "N/A".to_string(), label),
+                );
+                row.on_site = Some(on_site as i8);
+                self.pending.apply(&data.operation_tool, op_tool_id, row, description)
+                    .map(|raw_string| ("Update", TableTarget::OperationTool, raw_string))
+            },
+            DeskRequest::RaiseAlert { op_id, category } => {
+                let alert = Alert {
+                    category: Some(category),
+                    operation_id: Some(op_id),
+                    ..Alert::default()
+                };
+                serde_json::to_string(&alert).map(|raw_string| ("Alert", TableTarget::Alert, raw_string))
+            },
+        };
+        match sent {
+            Ok((method, table_name, raw_string)) => self.send(method, Some(json!({ "table_name": table_name, "data": raw_string }))),
+            Err(err) => println!("err sending request: {}", err),
+        }
+    }
+    /// Draws one of the operation boards: search bar, back button and the
+    /// table at the end of its drill-down tree, starting from `root`.
+    fn scope_window(&mut self, ctx: &egui::Context, central_window: CentralWindowEnum, title: &str, root: WindowTable) {
+        if !*self.central_panel_window_show.show_mut(central_window) {
+            return;
+        }
+        if let Some(data) = &mut self.data {
+            if !self.central_panel_window_show.has_tree(central_window) {
+                self.central_panel_window_show.initial_tree(central_window, TableTree::new(data, root, None));
+            }
+        }
+        egui::Window::new(title)
+        .id(egui::Id::new(format!("{:?}", central_window)))
+        .resizable(true)
+        .constrain(true)
+        .collapsible(true)
+        .title_bar(true)
+        .scroll(false)
+        .enabled(true)
+        .show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("🔎");
+                ui.add(egui::TextEdit::singleline(self.central_panel_window_show.search_input_mut(central_window)).hint_text("room:OR-2 status:borrowed ready<50% starts:today"));
+                if ui.button("help").clicked() {
+                    self.central_panel_window_show.filter_help.show = !self.central_panel_window_show.filter_help.show;
+                }
+                if !self.central_panel_window_show.is_root_state(central_window) && ui.button("back").clicked() {
+                    self.central_panel_window_show.remove_last(central_window);
+                }
+            });
+            let breadcrumb = self.central_panel_window_show.breadcrumb(central_window);
+            if !breadcrumb.is_empty() {
+                ui.weak(format!("{} › {}", title, breadcrumb.join(" › ")));
+            }
+            let filter = Filter::parse(self.central_panel_window_show.search_input(central_window));
+            for err in &filter.errors {
+                ui.colored_label(Color32::from_rgb(255, 46, 32), format!("⚠ ignored {}", err));
+            }
+            let sort = self.central_panel_window_show.sort(central_window);
+            let option_data = self.central_panel_window_show.display_last(central_window)
+                .map(|window_table| {
+                    let mut window_table = window_table.filter(&filter);
+                    if let Some(sort) = sort {
+                        window_table.sort(sort);
+                    }
+                    window_table
+                });
+            if let (Some(option_data), Some(data)) = (option_data, &mut self.data) {
+                TableData::build_table(
+                    ui,
+                    option_data,
+                    central_window,
+                    &mut self.central_panel_window_show,
+                    data
+                );
+            }
+        });
     }
 }
 
 impl App for FrontdeskApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        if self.stale_since.is_some() {
+            return;
+        }
+        if let Some(data) = &self.data {
+            match StoredSnapshot::new(data.clone()).seal() {
+                Ok(sealed) => storage.set_string(STORAGE_KEY, sealed),
+                Err(err) => println!("err storing snapshot: {}", err),
+            }
+        }
+    }
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         if let Some(msg) = self.receiver.try_recv() {
             match msg {
@@ -131,58 +346,87 @@ impl App for FrontdeskApp {
                 ewebsock::WsEvent::Message(text) => {
                     println!("text: {:?}", text);
                     match text {
-                        ewebsock::WsMessage::Binary(vec) => todo!(),
+                        ewebsock::WsMessage::Binary(_) => todo!(),
                         ewebsock::WsMessage::Text(text) => {
                             println!("text: {:?}", text);
-                            match serde_json::from_str::<EncryptedText>(&text) {
-                                Ok(encrypted_text) => {
-                                    if let Ok(key) = &generate_fixed_key() {
-                                        if let Ok(decrypted_text) = decrypt_message(key, &encrypted_text.nonce, &encrypted_text.cipher_text) {
-                                            match serde_json::from_str::<ReceiveMessage>(&decrypted_text) {
-                                                Ok(message) => {
-                                                    println!("message: {:?}", message);
-                                                    match message.operation {
-                                                        Operation::Initialize => {
-                                                            if let Some(data) = &mut self.data {
-                                                                data.initialize(message.data);
-                                                            } else {
-                                                                let mut new_table_data = TableData::new();
-                                                                new_table_data.initialize(message.data);
-                                                                self.data = Some(new_table_data);
-                                                                println!("self.data: {:?}", self.data);
+                            if let Ok(encrypted_text) = serde_json::from_str::<EncryptedText>(&text) {
+                                if let Ok(key) = &generate_fixed_key() {
+                                    if let Ok(decrypted_text) = decrypt_message(key, &encrypted_text.nonce, &encrypted_text.cipher_text) {
+                                        match serde_json::from_str::<ReceiveMessage>(&decrypted_text) {
+                                            Ok(message) => {
+                                                println!("message: {:?}", message);
+                                                match message.operation {
+                                                    Operation::Initialize => {
+                                                        let before = self.data.as_ref().map(SnapshotDiff::capture);
+                                                        let data = self.data.get_or_insert_with(TableData::new);
+                                                        match data.initialize(message.data) {
+                                                            Ok(report) => {
+                                                                for rejected in &report.rejected {
+                                                                    println!("err parsing: {}", rejected);
+                                                                }
+                                                                if let Some(before) = before {
+                                                                    let diff = SnapshotDiff::between(before, data);
+                                                                    if !diff.is_empty() {
+                                                                        if self.snapshot_diffs.len() == SNAPSHOT_DIFF_HISTORY {
+                                                                            self.snapshot_diffs.remove(0);
+                                                                        }
+                                                                        self.snapshot_diffs.push(diff);
+                                                                        self.central_panel_window_show.snapshot_diff.selected = None;
+                                                                    }
+                                                                }
+                                                                self.load_report = Some(report);
+                                                                self.snapshot_error = None;
+                                                                self.stale_since = None;
+                                                            },
+                                                            Err(err) => {
+                                                                println!("err parsing: initialize {}", err);
+                                                                self.snapshot_error = Some(err.to_string());
+                                                            },
+                                                        }
+                                                    },
+                                                    Operation::Update => {
+                                                        if let Some(data) = &self.data {
+                                                            for undone in self.pending.resolve(data, &message) {
+                                                                self.central_panel_window_show.toasts.push(format!("⚠ the server refused {}, undone", undone.description));
                                                             }
-                                                        },
-                                                        Operation::Update => {},
-                                                    }
-                                                },
-                                                Err(_) => {
-                                                    println!("err parsing: ReceiveMessage");
-                                                },
-                                            }
+                                                            if message.is_rejected() {
+                                                                println!("err server rejected update: {} {}", message.status_code, message.data);
+                                                            } else if let Err(err) = data.update(message.data, message.table_name) {
+                                                                println!("err parsing: update row {:?}", err);
+                                                            }
+                                                        }
+                                                    },
+                                                    Operation::Insert => {
+                                                        if let Some(data) = &self.data {
+                                                            if let Err(err) = data.insert(message.data, message.table_name) {
+                                                                println!("err parsing: insert row {:?}", err);
+                                                            }
+                                                        }
+                                                    },
+                                                    Operation::Delete => {
+                                                        if let Some(data) = &self.data {
+                                                            if let Err(err) = data.delete(message.data, message.table_name) {
+                                                                println!("err parsing: delete row {:?}", err);
+                                                            }
+                                                        }
+                                                    },
+                                                }
+                                            },
+                                            Err(_) => {
+                                                println!("err parsing: ReceiveMessage");
+                                            },
                                         }
                                     }
-                                },
-                                Err(_) => {
-                                    
-                                },
+                                }
                             }
                         },
                         ewebsock::WsMessage::Unknown(_) => todo!(),
-                        ewebsock::WsMessage::Ping(vec) => todo!(),
-                        ewebsock::WsMessage::Pong(vec) => todo!(),
+                        ewebsock::WsMessage::Ping(_) => todo!(),
+                        ewebsock::WsMessage::Pong(_) => todo!(),
                     }
                 },
                 ewebsock::WsEvent::Error(_) => {
-                    let options = ewebsock::Options::default();
-                    let (mut sender, receiver) = ewebsock::connect("ws://127.0.0.15:8080", options).unwrap();
-                    
-                    let request_json = serde_json::to_string(&SendMessage {
-                        level: "Frontdesk".to_string(),
-                        method: "Initial".to_string(),
-                        data: Some(json!({"content": "Hello from button('Send Message')!"})),
-                    }).unwrap();
-                    sender.send(ewebsock::WsMessage::Text(request_json));
-
+                    let (sender, receiver) = connect(ctx);
                     self.sender = sender;
                     self.receiver = receiver;
                 },
@@ -205,125 +449,110 @@ impl App for FrontdeskApp {
                         if ui.button("〰 Pre-Operative").clicked() {
                             self.toggle_window(CentralWindowEnum::PreOperative);
                         }; 
-                        let _ = ui.button("⛔ post-operative");
-                        let _ = ui.button("✚ recovery");
-                        let _ = ui.button("✅ discharge");
+                        if ui.button("⛔ post-operative").clicked() {
+                            self.toggle_window(CentralWindowEnum::PostOperative);
+                        };
+                        if ui.button("✚ recovery").clicked() {
+                            self.toggle_window(CentralWindowEnum::Recovery);
+                        };
+                        if ui.button("✅ discharge").clicked() {
+                            self.toggle_window(CentralWindowEnum::Discharge);
+                        };
                     });
                 }
             );
 
+            let conflict_count = self.data.as_ref().map_or(0, |data| data.conflicts().len());
+            if ui.button(format!("⚠ Conflicts ({})", conflict_count)).clicked() {
+                self.central_panel_window_show.conflicts.show = !self.central_panel_window_show.conflicts.show;
+            }
+            if ui.button("🩺 Data health").clicked() {
+                self.central_panel_window_show.data_health.show = !self.central_panel_window_show.data_health.show;
+            }
+            if ui.button("🔀 Snapshot changes").clicked() {
+                self.central_panel_window_show.snapshot_diff.show = !self.central_panel_window_show.snapshot_diff.show;
+            }
+            #[cfg(feature = "sql-mirror")]
+            if ui.button("🗄 SQL console").clicked() {
+                self.central_panel_window_show.sql_console.show = !self.central_panel_window_show.sql_console.show;
+            }
+
             if ui.button("Send Message").clicked() {
-                let request_json = serde_json::to_string(&SendMessage {
-                    level: "Frontdesk".to_string(),
-                    method: "Alert".to_string(),
-                    data: None,
-                }).unwrap();
-                self.sender.send(ewebsock::WsMessage::Text(request_json));
+                self.send("Alert", None);
             }
         });
         egui::TopBottomPanel::top("my_panel").show(ctx, |ui| {
-            ui.label("Hello World!");
-        });
-        egui::CentralPanel::default().show(ctx, |ui| {
-            if self.central_panel_window_show.in_progress.show {
-                egui::Window::new("❕ In-progress")
-                    .id(egui::Id::new("in_progress")) // unique id for the window
-                    .resizable(true)
-                    .constrain(true)
-                    .collapsible(true)
-                    .title_bar(true)
-                    .scroll(false)
-                    .enabled(true)
-                    .show(ctx, |ui| {
-                        TableBuilder::new(ui)
-                            .column(Column::auto().resizable(false))
-                            .column(Column::auto().resizable(false))
-                            .column(Column::auto().resizable(false))
-                            .column(Column::auto().resizable(false))
-                            .column(Column::auto().resizable(false))
-                            .header(20.0, |mut header| {
-                                let headings = ["label", "patient full name", "room name", "start time", "end time"];
-                                for title in headings {
-                                    header.col(|ui| {
-                                        ui.horizontal(|ui|{
-                                            ui.heading(title);
-                                            ui.button("🔁");
-                                        });
-                                    });
-                                }
-                            })
-
-                            .body(|mut body| {
-                                if let Some(table_data) = &self.data {
-                                    let rows = table_data.equipment.read().unwrap();
-                                    //for content in &*rows {
-                                    //    if content.status.clone().unwrap() != "in-progress" {
-                                    //        continue;
-                                    //    }
-                                    //    body.row(30.0, |mut row| {
-                                    //        row.col(|ui| {
-                                    //            ui.label(content.label.clone().unwrap_or_default());
-                                    //        });
-                                    //        row.col(|ui| {
-                                    //            ui.label(content.patient_full_name.clone().unwrap_or_default());
-                                    //        });
-                                    //        row.col(|ui| {
-                                    //            ui.label(content.room_name.clone().unwrap_or_default());
-                                    //        });
-                                    //        row.col(|ui| {
-                                    //            ui.label(format_date(&content.start_time.clone().unwrap_or_default()));
-                                    //        });
-                                    //        row.col(|ui| {
-                                    //            ui.label(format_date(&content.end_time.clone().unwrap_or_default()));
-                                    //        });
-                                    //    });
-                                    //}
+            ui.horizontal(|ui| {
+                ui.label("Hello World!");
+                if let Some(stale_since) = self.stale_since {
+                    ui.colored_label(Color32::from_rgb(246, 140, 46), format!("⏸ offline copy, {} old", format_age(Utc::now() - stale_since)))
+                        .on_hover_text(format!("saved {}, waiting for the server", format_date(Some((stale_since + chrono::Duration::hours(8)).naive_utc()))));
+                }
+                if let Some(err) = &self.snapshot_error {
+                    ui.colored_label(Color32::from_rgb(255, 46, 32), "⚠ last snapshot was unusable, showing previous data")
+                        .on_hover_text(err);
+                }
+                if let Some(report) = &self.load_report {
+                    if !report.is_clean() {
+                        ui.colored_label(Color32::from_rgb(246, 140, 46), format!("⚠ {} rows rejected", report.rejected.len()))
+                            .on_hover_ui(|ui| {
+                                for rejected in &report.rejected {
+                                    ui.label(rejected.to_string());
                                 }
                             });
-                    });
-            }
-            if self.central_panel_window_show.pre_operative.show {
-                let mut window_table = WindowTable::PreOperativeDefault(None);
-                if self.central_panel_window_show.pre_operative.tree.is_none() {
-                    if let Some(data) = &mut self.data {
-                        self.central_panel_window_show.initial_tree(CentralWindowEnum::PreOperative, data.query(&mut WindowTable::PreOperativeDefault(None), None));
                     }
                 }
-                egui::Window::new("〰 Pre-Operative")
-                .id(egui::Id::new("pre_operative")) 
-                .resizable(true)
-                .constrain(true)
-                .collapsible(true)
-                .title_bar(true)
-                .scroll(false)
-                .enabled(true)
-                .show(ctx, |ui| {
-                    ui.horizontal(|ui| {
-                        ui.label("🔎");
-                        ui.text_edit_singleline(&mut self.central_panel_window_show.pre_operative.search_input);
-                        if ui.button("help").clicked() {
-                            
-                        }
-                        if !self.central_panel_window_show.is_root_state(CentralWindowEnum::PreOperative) {
-                            if ui.button("back").clicked() {
-                                println!("backedd");
-                                self.central_panel_window_show.remove_last(CentralWindowEnum::PreOperative);
-                            }
-                        }    
-                    });
-                    let mut option_data = self.central_panel_window_show.display_last(CentralWindowEnum::PreOperative);
-                    if let (Some(option_data), Some(data)) = (&mut option_data, &mut self.data) {
-                        TableData::build_table(
-                            ui, 
-                            option_data.to_owned(),
-                            &mut self.central_panel_window_show,
-                            data
-                        );
-
-                    }
-                });
+            });
+        });
+        let mut opened = None;
+        if let Some(data) = &self.data {
+            window::health::show(ctx, &mut self.central_panel_window_show.data_health, data, self.load_report.as_ref());
+            opened = window::conflicts::show(ctx, &mut self.central_panel_window_show.conflicts, data);
+            #[cfg(feature = "sql-mirror")]
+            if let Some(mirror) = &mut self.mirror {
+                if let Err(err) = mirror.sync(data) {
+                    println!("err syncing mirror: {}", err);
+                }
+            }
+        }
+        if let Some(op_id) = opened {
+            self.open_operation(op_id);
+        }
+        window::diff::show(ctx, &mut self.central_panel_window_show.snapshot_diff, &self.snapshot_diffs);
+        #[cfg(feature = "sql-mirror")]
+        window::sql_console::show(ctx, &mut self.central_panel_window_show.sql_console, &mut self.mirror);
+        window::filter_help::show(ctx, &mut self.central_panel_window_show.filter_help);
+        if let Some(data) = &mut self.data {
+            for undone in self.pending.expire(data) {
+                self.central_panel_window_show.toasts.push(format!("⚠ no answer from the server for {}, undone", undone.description));
+            }
+            // once per frame for every board, before any of them is drawn
+            self.central_panel_window_show.refresh(data);
+        }
+        egui::CentralPanel::default().show(ctx, |_ui| {
+            self.scope_window(ctx, CentralWindowEnum::InProgress, "❕ In-progress", WindowTable::InProgressDefault(None));
+            self.scope_window(ctx, CentralWindowEnum::PreOperative, "〰 Pre-Operative", WindowTable::PreOperativeDefault(None));
+            self.scope_window(ctx, CentralWindowEnum::PostOperative, "⛔ Post-Operative", WindowTable::PostOperativeDefault(None));
+            self.scope_window(ctx, CentralWindowEnum::Recovery, "✚ Recovery", WindowTable::RecoveryDefault(None));
+            self.scope_window(ctx, CentralWindowEnum::Discharge, "✅ Discharged today", WindowTable::DischargeDefault(None));
+            if self.central_panel_window_show.scope(CentralWindowEnum::InProgress).show {
+                // elapsed time moves even when no data arrives
+                ctx.request_repaint_after(std::time::Duration::from_secs(30));
             }
         });
+        let requests = std::mem::take(&mut self.central_panel_window_show.requests);
+        if !requests.is_empty() {
+            for request in requests {
+                self.handle_request(request);
+            }
+            // the next frame refreshes the boards with the optimistic rows
+            ctx.request_repaint();
+        }
+        window::toast::show(ctx, &mut self.central_panel_window_show.toasts);
+        if !self.pending.is_empty() {
+            // expire unanswered updates even if nothing else happens
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        }
     }
 }
 
@@ -0,0 +1,209 @@
+use chrono::{Datelike, Duration, NaiveDateTime};
+use egui::{pos2, vec2, Align2, Color32, FontId, Rect, Rounding, Sense, Stroke, Ui};
+
+use crate::{database::table::OperationStatus, format_date, local_now, table::query_return::{CalendarBlock, CalendarLane}};
+
+const LANE_LABEL_WIDTH: f32 = 120.0;
+const LANE_HEIGHT: f32 = 36.0;
+const AXIS_HEIGHT: f32 = 20.0;
+
+/// Span and day the calendar shows, kept while the view is open.
+#[derive(Debug, Clone, Default)]
+pub struct CalendarState {
+    pub week: bool,
+    /// Days away from today the calendar is scrolled to.
+    pub day_offset: i64,
+}
+impl CalendarState {
+    fn range(&self) -> (NaiveDateTime, NaiveDateTime) {
+        let day = local_now().date() + Duration::days(self.day_offset);
+        let first = if self.week {
+            day - Duration::days(day.weekday().num_days_from_monday() as i64)
+        } else {
+            day
+        };
+        let start = first.and_hms_opt(0, 0, 0).unwrap_or_default();
+        (start, start + Duration::days(if self.week { 7 } else { 1 }))
+    }
+}
+
+fn status_color(status: Option<&OperationStatus>) -> Color32 {
+    match status {
+        Some(OperationStatus::PreOperative) => Color32::from_rgb(246, 140, 46),
+        Some(OperationStatus::InProgress) => Color32::from_rgb(0, 140, 26),
+        Some(OperationStatus::PostOperative) => Color32::from_rgb(120, 90, 200),
+        Some(OperationStatus::Recovery) => Color32::from_rgb(40, 120, 220),
+        Some(OperationStatus::Discharge) | None => Color32::GRAY,
+    }
+}
+
+fn readiness_color(on_site_percentage: f64) -> Color32 {
+    if on_site_percentage >= 100.0 {
+        Color32::from_rgb(0, 140, 26)
+    } else if on_site_percentage >= 50.0 {
+        Color32::from_rgb(246, 140, 46)
+    } else {
+        Color32::from_rgb(255, 46, 32)
+    }
+}
+
+/// What the desk did to a block.
+pub enum CalendarAction {
+    Open(i32),
+    /// A block dropped at another time or on another room's lane.
+    Move {
+        op_id: i32,
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+        room_id: Option<i32>,
+    },
+}
+
+/// Paints one lane per room, or per member of staff, with a block per
+/// operation and a line at the current time. Blocks open on click and can
+/// be dragged to reschedule.
+pub fn calendar(ui: &mut Ui, lanes: &[CalendarLane], state: &mut CalendarState) -> Option<CalendarAction> {
+    ui.horizontal(|ui| {
+        ui.selectable_value(&mut state.week, false, "day");
+        ui.selectable_value(&mut state.week, true, "week");
+        if ui.button("◀").clicked() {
+            state.day_offset -= if state.week { 7 } else { 1 };
+        }
+        if ui.button("today").clicked() {
+            state.day_offset = 0;
+        }
+        if ui.button("▶").clicked() {
+            state.day_offset += if state.week { 7 } else { 1 };
+        }
+        let (start, end) = state.range();
+        if state.week {
+            ui.label(format!("{} – {}", start.format("%b %-d"), (end - Duration::days(1)).format("%b %-d, %Y")));
+        } else {
+            ui.label(start.format("%A, %b %-d, %Y").to_string());
+        }
+    });
+
+    let (range_start, range_end) = state.range();
+    let range_seconds = (range_end - range_start).num_seconds() as f32;
+    let width = ui.available_width().max(LANE_LABEL_WIDTH + 200.0);
+    let (rect, _) = ui.allocate_exact_size(vec2(width, AXIS_HEIGHT + LANE_HEIGHT * lanes.len() as f32), Sense::hover());
+    let painter = ui.painter_at(rect);
+    let timeline = Rect::from_min_max(pos2(rect.left() + LANE_LABEL_WIDTH, rect.top()), rect.max);
+    let x_of = |time: NaiveDateTime| {
+        let seconds = (time - range_start).num_seconds() as f32;
+        timeline.left() + timeline.width() * (seconds / range_seconds).clamp(0.0, 1.0)
+    };
+    let grid = ui.visuals().widgets.noninteractive.bg_stroke;
+    let text_color = ui.visuals().text_color();
+    let small = FontId::proportional(11.0);
+
+    // hour or day ticks along the top
+    let (tick, tick_count) = if state.week { (Duration::days(1), 7) } else { (Duration::hours(2), 12) };
+    for step in 0..=tick_count {
+        let time = range_start + tick * step;
+        let x = x_of(time);
+        painter.line_segment([pos2(x, timeline.top()), pos2(x, rect.bottom())], grid);
+        if step < tick_count {
+            let label = if state.week { time.format("%a %-d").to_string() } else { time.format("%H:%M").to_string() };
+            painter.text(pos2(x + 2.0, rect.top() + 2.0), Align2::LEFT_TOP, label, small.clone(), text_color);
+        }
+    }
+
+    let mut action = None;
+    for (index, lane) in lanes.iter().enumerate() {
+        let top = rect.top() + AXIS_HEIGHT + LANE_HEIGHT * index as f32;
+        painter.line_segment([pos2(rect.left(), top), pos2(rect.right(), top)], grid);
+        painter.text(pos2(rect.left() + 4.0, top + LANE_HEIGHT / 2.0), Align2::LEFT_CENTER, &lane.name, FontId::proportional(13.0), text_color);
+
+        for block in &lane.blocks {
+            let (Some(start_time), Some(end_time)) = (block.start_time, block.end_time) else {
+                continue;
+            };
+            if end_time <= range_start || start_time >= range_end {
+                continue;
+            }
+            let block_rect = Rect::from_min_max(
+                pos2(x_of(start_time), top + 3.0),
+                pos2(x_of(end_time).max(x_of(start_time) + 3.0), top + LANE_HEIGHT - 3.0),
+            );
+            painter.rect_filled(block_rect, Rounding::same(3.0), status_color(block.op_status.as_ref()));
+            let readiness = Rect::from_min_size(
+                pos2(block_rect.left(), block_rect.bottom() - 4.0),
+                vec2(block_rect.width() * (block.on_site_percentage / 100.0).clamp(0.0, 1.0) as f32, 4.0),
+            );
+            painter.rect_filled(readiness, Rounding::ZERO, readiness_color(block.on_site_percentage));
+            painter.with_clip_rect(block_rect.intersect(rect))
+                .text(block_rect.left_center() + vec2(4.0, -2.0), Align2::LEFT_CENTER, &block.op_label, small.clone(), Color32::WHITE);
+
+            // an operation sits on the lane of every staff member of it, so the lane is part of the id
+            let response = ui.interact(block_rect, ui.id().with(("calendar_block", index, block.op_id)), Sense::click_and_drag())
+                .on_hover_text(block_tooltip(block));
+            if !block.room_conflicts.is_empty() || !block.staff_conflicts.is_empty() {
+                painter.rect_stroke(block_rect, Rounding::same(3.0), Stroke::new(2.0, Color32::from_rgb(255, 46, 32)));
+            }
+            if response.hovered() {
+                painter.rect_stroke(block_rect, Rounding::same(3.0), Stroke::new(2.0, text_color));
+            }
+            if response.clicked() {
+                action = block.op_id.map(CalendarAction::Open);
+            }
+            let pointer = ui.input(|input| input.pointer.press_origin().zip(input.pointer.interact_pos()));
+            let Some((origin, position)) = pointer.filter(|_| response.dragged() || response.drag_stopped()) else {
+                continue;
+            };
+            // snap to 5 minutes on a day, 15 on a week
+            let snap = if state.week { 15 * 60 } else { 5 * 60 };
+            let seconds = ((position.x - origin.x) / timeline.width() * range_seconds) as i64;
+            let shift = Duration::seconds((seconds as f64 / snap as f64).round() as i64 * snap);
+            let target = ((position.y - rect.top() - AXIS_HEIGHT) / LANE_HEIGHT).floor().clamp(0.0, (lanes.len() - 1) as f32) as usize;
+            // operations cannot be moved onto the lane of unknown rooms
+            let target_lane = if lanes[target].room_id.is_some() { target } else { index };
+            let ghost = Rect::from_min_max(
+                pos2(x_of(start_time + shift), top + 3.0),
+                pos2(x_of(end_time + shift).max(x_of(start_time + shift) + 3.0), top + LANE_HEIGHT - 3.0),
+            ).translate(vec2(0.0, LANE_HEIGHT * (target_lane as f32 - index as f32)));
+            painter.rect_stroke(ghost, Rounding::same(3.0), Stroke::new(2.0, status_color(block.op_status.as_ref())));
+            painter.text(ghost.left_top() - vec2(0.0, 2.0), Align2::LEFT_BOTTOM, (start_time + shift).format("%H:%M").to_string(), small.clone(), text_color);
+            let moved = shift != Duration::zero() || target_lane != index;
+            if response.drag_stopped() && moved && action.is_none() {
+                action = block.op_id.map(|op_id| CalendarAction::Move {
+                    op_id,
+                    start_time: start_time + shift,
+                    end_time: end_time + shift,
+                    room_id: lanes[target_lane].room_id,
+                });
+            }
+        }
+    }
+
+    let now = local_now();
+    if now >= range_start && now < range_end {
+        let x = x_of(now);
+        painter.line_segment([pos2(x, timeline.top()), pos2(x, rect.bottom())], Stroke::new(2.0, Color32::from_rgb(255, 46, 32)));
+    }
+    // keep the now line moving without input
+    ui.ctx().request_repaint_after(std::time::Duration::from_secs(30));
+
+    action
+}
+
+fn block_tooltip(block: &CalendarBlock) -> String {
+    let mut tooltip = format!(
+        "{}\n{}\n{}\n{} → {}\n{}, {:.0}% tools on site",
+        block.op_label,
+        block.patient_full_name,
+        block.room_name,
+        format_date(block.start_time),
+        format_date(block.end_time),
+        block.op_status.as_ref().map_or_else(|| "N/A".to_string(), |status| format!("{:?}", status)),
+        block.on_site_percentage,
+    );
+    if !block.room_conflicts.is_empty() {
+        tooltip.push_str(&format!("\n⚠ room double-booked with {}", block.room_conflicts.join(", ")));
+    }
+    if !block.staff_conflicts.is_empty() {
+        tooltip.push_str(&format!("\n⚠ staff busy elsewhere: {}", block.staff_conflicts.join(", ")));
+    }
+    tooltip
+}
+
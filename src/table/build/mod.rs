@@ -1,18 +1,30 @@
-use egui::{Button, Color32, Label, RichText, Sense, Ui};
-use egui_extras::{TableBuilder, Column};
+pub mod calendar;
 
-use crate::{date_code, format_date, table::TableData};
+use calendar::CalendarAction;
+use egui::{text::LayoutJob, Button, Color32, Label, ProgressBar, RichText, Sense, TextFormat, TextStyle, Ui};
+use egui_extras::{Column, TableBuilder, TableRow};
 
-use super::{query_return::WindowTable, CentralWindow, CentralWindowEnum, OperationStatus, Query};
+use crate::{database::table::{AlertCategory, OperationStatus}, date_code, format_age, format_date, local_now, table::TableData, window::{CentralWindow, CentralWindowEnum, DeskRequest}};
+
+use super::{filter::{Filter, TextField}, query_return::{TableTree, ToolOperation, WindowTable}, search::highlighted, sort::Sort};
+
+const HIGHLIGHT: Color32 = Color32::from_rgb(255, 214, 10);
 
 pub trait BuildTable {
-    fn build_table<'a>(ui: &'a mut Ui, window_table: WindowTable, central_window: &mut CentralWindow, data: &mut TableData);
+    /// Draws `window_table` inside the window `central_window_enum`, which
+    /// is where any drill-down gets pushed.
+    fn build_table(ui: &mut Ui, window_table: WindowTable, central_window_enum: CentralWindowEnum, central_window: &mut CentralWindow, data: &mut TableData);
 }
 
 impl BuildTable for TableData {
-    fn build_table<'a>(ui: &'a mut Ui, window_table: WindowTable, central_window: &mut CentralWindow, data: &mut TableData) -> () {
-        let table_return = if let WindowTable::PreOperativeDefault(Some(s)) = &window_table {
-            let tbl = TableBuilder::new(ui)
+    fn build_table(ui: &mut Ui, window_table: WindowTable, central_window_enum: CentralWindowEnum, central_window: &mut CentralWindow, data: &mut TableData) {
+        let filter = Filter::parse(central_window.search_input(central_window_enum));
+        if let WindowTable::PreOperativeDefault(Some(s)) = &window_table {
+            if ui.button("📅 calendar").clicked() {
+                central_window.push_last(central_window_enum, TableTree::new(data, WindowTable::CalendarVisualization(None), None));
+            }
+            TableBuilder::new(ui)
+            .column(Column::auto().resizable(false))
             .column(Column::auto().resizable(false))
             .column(Column::auto().resizable(false))
             .column(Column::auto().resizable(false))
@@ -25,16 +37,14 @@ impl BuildTable for TableData {
                     "LABEL",
                     "PATIENT FULL NAME",
                     "ROOM NAME",
+                    "READINESS",
                     "TOOLS READY",
                     "STARTING OPERATION",
                     "ENDING OPERATION",
                 ];                
+                let sort = central_window.sort_mut(central_window_enum);
                 for title in headings {
-                    header.col(|ui| {
-                        ui.horizontal(|ui|{
-                            ui.heading(title);
-                        });
-                    });
+                    sort_header(&mut header, title, sort);
                 }
             })
             .body(|mut body| {
@@ -43,40 +53,60 @@ impl BuildTable for TableData {
                     if content.op_status.clone() != OperationStatus::PreOperative {
                         continue;
                     }
-                    let date_color = date_code(
-                        &content.start_time.clone(),
-                        &content.end_time.clone()
-                    );
+                    let date_color = date_code(content.start_time, content.end_time);
                     body.row(30.0, |mut row| {
                 
                         row.col(|ui| {
-                            if ui.add(Button::new(content.op_label.clone()).sense(Sense::click()).fill(Color32::TRANSPARENT)).clicked() {
-                        
+                            if ui.add(Button::new(highlight(ui, &content.op_label, &filter, TextField::Label)).sense(Sense::click()).fill(Color32::TRANSPARENT)).on_hover_text("team of this operation").clicked() {
+                                central_window.push_last(central_window_enum, TableTree::new(data, WindowTable::OperationTeam(None), content.op_id));
+                            }
+                            if !content.staff_conflicts.is_empty() && ui.add(Button::new(RichText::new("⚠").color(Color32::from_rgb(246, 140, 46))).fill(Color32::TRANSPARENT))
+                                .on_hover_text(format!("staff busy elsewhere: {}", content.staff_conflicts.join(", ")))
+                                .clicked() {
+                                central_window.push_last(central_window_enum, TableTree::new(data, WindowTable::StaffTimeline(None), content.op_id));
                             }
                         });
                         row.col(|ui| {
-                            if ui.add(Button::new(content.patient_full_name.clone()).sense(Sense::click()).fill(Color32::TRANSPARENT)).clicked() {
-                        
+                            if ui.add(Button::new(highlight(ui, &content.patient_full_name, &filter, TextField::Patient)).sense(Sense::click()).fill(Color32::TRANSPARENT)).clicked() && content.patient_id.is_some() {
+                                central_window.push_last(central_window_enum, TableTree::new(data, WindowTable::PatientData(None), content.patient_id));
                             }
                         });
                         row.col(|ui| {
-                            if ui.add(Button::new(content.room_name.clone()).sense(Sense::click()).fill(Color32::TRANSPARENT)).clicked() {
-                        
+                            if ui.add(Button::new(highlight(ui, &content.room_name, &filter, TextField::Room)).sense(Sense::click()).fill(Color32::TRANSPARENT)).clicked() && content.room_id.is_some() {
+                                central_window.push_last(central_window_enum, TableTree::new(data, WindowTable::RoomProperty(None), content.room_id));
+                            }
+                            if !content.room_conflicts.is_empty() {
+                                ui.colored_label(Color32::from_rgb(255, 46, 32), "⚠")
+                                    .on_hover_text(format!("room double-booked with {}", content.room_conflicts.join(", ")));
                             }
                         });
                         row.col(|ui| {
-                            if ui.add(Button::new(content.on_site_percentage.clone().to_string()).sense(Sense::click()).fill(Color32::TRANSPARENT)).clicked() {
-                                central_window.push_last(CentralWindowEnum::PreOperative, data.query(&mut WindowTable::PreOperativeToolReady(None), content.op_id.clone()));
+                            let (badge, fill) = if content.readiness.is_go() {
+                                ("GO", Color32::from_rgb(0, 140, 26))
+                            } else {
+                                ("NO-GO", Color32::from_rgb(255, 46, 32))
+                            };
+                            let badge = ui.add(Button::new(RichText::new(badge).color(Color32::WHITE).strong()).fill(fill).sense(Sense::hover()));
+                            if content.readiness.is_go() {
+                                badge.on_hover_text("tools, staff, room and patient are all set");
+                            } else {
+                                badge.on_hover_text(content.readiness.missing.iter().map(|missing| format!("• {}", missing)).collect::<Vec<String>>().join("\n"));
                             }
                         });
                         row.col(|ui| {
-                            let text = RichText::new(format_date(&content.start_time.clone())).color(date_color);
+                            let text = format!("{}/{} ({:.0}%)", content.on_site_tools, content.total_tools, content.on_site_percentage);
+                            if ui.add(Button::new(text).sense(Sense::click()).fill(Color32::TRANSPARENT)).clicked() {
+                                central_window.push_last(central_window_enum, TableTree::new(data, WindowTable::PreOperativeToolReady(None), content.op_id));
+                            }
+                        });
+                        row.col(|ui| {
+                            let text = RichText::new(format_date(content.start_time)).color(date_color);
                             if ui.add(Button::new(text).sense(Sense::click()).fill(Color32::TRANSPARENT)).clicked() {
                         
                             }
                         });
                         row.col(|ui| {
-                            let text = RichText::new(format_date(&content.end_time.clone())).color(date_color);
+                            let text = RichText::new(format_date(content.end_time)).color(date_color);
                             if ui.add(Button::new(text).sense(Sense::click()).fill(Color32::TRANSPARENT)).clicked() {
                         
                             }
@@ -84,35 +114,40 @@ impl BuildTable for TableData {
                     });
                 }
             });
-            tbl
         } else if let WindowTable::PreOperativeToolReady(Some(s)) = &window_table { 
-            let tbl = TableBuilder::new(ui)
+            TableBuilder::new(ui)
             .column(Column::auto().resizable(false))
             .column(Column::auto().resizable(false))
             .column(Column::auto().resizable(false))
             .striped(true)
             .header(20.0, |mut header| {
                 let headings = ["equipment name", "equipment on site", "tool status"];
+                let sort = central_window.sort_mut(central_window_enum);
                 for title in headings {
-                    header.col(|ui| {
-                        ui.horizontal(|ui|{
-                            ui.heading(title);
-                        });
-                    });
+                    sort_header(&mut header, title, sort);
                 }
             })
             .body(|mut body| {
                 for content in s {
                     body.row(30.0, |mut row| {
                         row.col(|ui| {
-                            ui.add(Label::new(content.equipment_name.clone()));
+                            if ui.add(Button::new(highlight(ui, &content.equipment_name, &filter, TextField::Equipment)).sense(Sense::click()).fill(Color32::TRANSPARENT)).on_hover_text("details of this tool").clicked() && content.tool_id.is_some() {
+                                central_window.push_last(central_window_enum, TableTree::new(data, WindowTable::ToolDetail(None), content.tool_id));
+                            }
+                            if !content.conflicts.is_empty() {
+                                ui.colored_label(Color32::from_rgb(255, 46, 32), "⚠")
+                                    .on_hover_text(content.conflicts.join("\n"));
+                            }
                         });
                         row.col(|ui| {
                             let text = RichText::new(if content.on_site { "Yes" } else { "No" })
                             .color(Color32::from_rgb(246, 140, 46))
                             .underline();
-                            if ui.add(Button::new(text).sense(Sense::click()).fill(Color32::TRANSPARENT)).clicked() {
-                                
+                            let hover = if content.on_site { "mark as not on site" } else { "mark as delivered" };
+                            if ui.add(Button::new(text).sense(Sense::click()).fill(Color32::TRANSPARENT)).on_hover_text(hover).clicked() {
+                                if let Some(op_tool_id) = content.op_tool_id {
+                                    central_window.requests.push(DeskRequest::SetOnSite { op_tool_id, on_site: !content.on_site });
+                                }
                             }
                         });
                         row.col(|ui| {
@@ -121,9 +156,502 @@ impl BuildTable for TableData {
                     });
                 }
             });
-            tbl
+        } else if let WindowTable::InProgressDefault(Some(s)) = &window_table {
+            let now = local_now();
+            TableBuilder::new(ui)
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .striped(true)
+            .header(20.0, |mut header| {
+                let headings = [
+                    "LABEL",
+                    "PATIENT FULL NAME",
+                    "ROOM NAME",
+                    "STAFF",
+                    "ELAPSED / PLANNED",
+                    "ENDING OPERATION",
+                ];
+                let sort = central_window.sort_mut(central_window_enum);
+                for title in headings {
+                    sort_header(&mut header, title, sort);
+                }
+            })
+            .body(|mut body| {
+                for content in s {
+                    let date_color = date_code(content.start_time, content.end_time);
+                    body.row(30.0, |mut row| {
+                        row.col(|ui| {
+                            if ui.add(Button::new(highlight(ui, &content.op_label, &filter, TextField::Label)).sense(Sense::click()).fill(Color32::TRANSPARENT)).on_hover_text("tools of this operation").clicked() {
+                                central_window.push_last(central_window_enum, TableTree::new(data, WindowTable::PreOperativeToolReady(None), content.op_id));
+                            }
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(highlight(ui, &content.patient_full_name, &filter, TextField::Patient)));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(highlight(ui, &content.room_name, &filter, TextField::Room)));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(content.staff_count.to_string())).on_hover_text(content.staff_names.clone());
+                            if !content.staff_conflicts.is_empty() && ui.add(Button::new(RichText::new("⚠").color(Color32::from_rgb(246, 140, 46))).fill(Color32::TRANSPARENT))
+                                .on_hover_text(format!("staff busy elsewhere: {}", content.staff_conflicts.join(", ")))
+                                .clicked() {
+                                central_window.push_last(central_window_enum, TableTree::new(data, WindowTable::StaffTimeline(None), content.op_id));
+                            }
+                        });
+                        row.col(|ui| {
+                            let (Some(start_time), Some(end_time)) = (content.start_time, content.end_time) else {
+                                ui.add(Label::new("N/A"));
+                                return;
+                            };
+                            let elapsed = now - start_time;
+                            let planned = end_time - start_time;
+                            let fraction = content.progress(now).unwrap_or(1.0);
+                            let text = format!("{} / {}", format_age(elapsed), format_age(planned));
+                            let color = if fraction > 1.0 { Color32::from_rgb(255, 46, 32) } else { Color32::from_rgb(0, 140, 26) };
+                            ui.add(ProgressBar::new(fraction.clamp(0.0, 1.0)).desired_width(160.0).fill(color).text(text));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(RichText::new(format_date(content.end_time)).color(date_color)));
+                        });
+                    });
+                }
+            });
+        } else if let WindowTable::PostOperativeDefault(Some(s)) = &window_table {
+            TableBuilder::new(ui)
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .striped(true)
+            .header(20.0, |mut header| {
+                let headings = [
+                    "LABEL",
+                    "PATIENT FULL NAME",
+                    "ROOM NAME",
+                    "STARTING OPERATION",
+                    "ENDING OPERATION",
+                ];
+                let sort = central_window.sort_mut(central_window_enum);
+                for title in headings {
+                    sort_header(&mut header, title, sort);
+                }
+            })
+            .body(|mut body| {
+                for content in s {
+                    body.row(30.0, |mut row| {
+                        row.col(|ui| {
+                            if ui.add(Button::new(highlight(ui, &content.op_label, &filter, TextField::Label)).sense(Sense::click()).fill(Color32::TRANSPARENT)).on_hover_text("tools of this operation").clicked() {
+                                central_window.push_last(central_window_enum, TableTree::new(data, WindowTable::PreOperativeToolReady(None), content.op_id));
+                            }
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(highlight(ui, &content.patient_full_name, &filter, TextField::Patient)));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(highlight(ui, &content.room_name, &filter, TextField::Room)));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(format_date(content.start_time)));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(format_date(content.end_time)));
+                        });
+                    });
+                }
+            });
+        } else if let WindowTable::RecoveryDefault(Some(s)) = &window_table {
+            TableBuilder::new(ui)
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .striped(true)
+            .header(20.0, |mut header| {
+                let headings = [
+                    "LABEL",
+                    "PATIENT FULL NAME",
+                    "WARD ROOM",
+                    "ASSISTANTS",
+                    "OPERATION ENDED",
+                ];
+                let sort = central_window.sort_mut(central_window_enum);
+                for title in headings {
+                    sort_header(&mut header, title, sort);
+                }
+            })
+            .body(|mut body| {
+                for content in s {
+                    body.row(30.0, |mut row| {
+                        row.col(|ui| {
+                            if ui.add(Button::new(highlight(ui, &content.op_label, &filter, TextField::Label)).sense(Sense::click()).fill(Color32::TRANSPARENT)).on_hover_text("tools of this operation").clicked() {
+                                central_window.push_last(central_window_enum, TableTree::new(data, WindowTable::PreOperativeToolReady(None), content.op_id));
+                            }
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(highlight(ui, &content.patient_full_name, &filter, TextField::Patient)));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(highlight(ui, &content.ward_room_name, &filter, TextField::Room)));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(content.assistant_names.clone()));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(format_date(content.end_time)));
+                        });
+                    });
+                }
+            });
+        } else if let WindowTable::DischargeDefault(Some(s)) = &window_table {
+            TableBuilder::new(ui)
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .striped(true)
+            .header(20.0, |mut header| {
+                let headings = [
+                    "LABEL",
+                    "PATIENT FULL NAME",
+                    "PHONE",
+                    "ROOM NAME",
+                    "DISCHARGED",
+                ];
+                let sort = central_window.sort_mut(central_window_enum);
+                for title in headings {
+                    sort_header(&mut header, title, sort);
+                }
+            })
+            .body(|mut body| {
+                for content in s {
+                    body.row(30.0, |mut row| {
+                        row.col(|ui| {
+                            if ui.add(Button::new(highlight(ui, &content.op_label, &filter, TextField::Label)).sense(Sense::click()).fill(Color32::TRANSPARENT)).on_hover_text("tools of this operation").clicked() {
+                                central_window.push_last(central_window_enum, TableTree::new(data, WindowTable::PreOperativeToolReady(None), content.op_id));
+                            }
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(highlight(ui, &content.patient_full_name, &filter, TextField::Patient)));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(content.patient_phone.clone()));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(highlight(ui, &content.room_name, &filter, TextField::Room)));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(format_date(content.end_time)));
+                        });
+                    });
+                }
+            });
+        } else if let WindowTable::PatientData(Some(patient)) = &window_table {
+            ui.heading(patient.full_name.clone());
+            egui::Grid::new("patient_data_contact").num_columns(2).striped(true).show(ui, |ui| {
+                ui.label("email");
+                ui.label(patient.email.clone());
+                ui.end_row();
+                ui.label("phone");
+                ui.label(patient.phone.clone());
+                ui.end_row();
+                ui.label("ward room");
+                ui.label(patient.ward_room_name.clone());
+                ui.end_row();
+                ui.label("assistants");
+                ui.vertical(|ui| {
+                    if patient.assistants.is_empty() {
+                        ui.label("none");
+                    }
+                    for assistant in &patient.assistants {
+                        ui.label(format!("{} · {}", assistant.full_name, assistant.phone));
+                    }
+                });
+                ui.end_row();
+            });
+            ui.separator();
+            ui.strong(format!("operations ({})", patient.history.len()));
+            TableBuilder::new(ui)
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .striped(true)
+            .header(20.0, |mut header| {
+                let headings = [
+                    "LABEL",
+                    "STATUS",
+                    "ROOM NAME",
+                    "STARTING OPERATION",
+                    "ENDING OPERATION",
+                ];
+                let sort = central_window.sort_mut(central_window_enum);
+                for title in headings {
+                    sort_header(&mut header, title, sort);
+                }
+            })
+            .body(|mut body| {
+                for content in &patient.history {
+                    body.row(30.0, |mut row| {
+                        row.col(|ui| {
+                            if ui.add(Button::new(highlight(ui, &content.op_label, &filter, TextField::Label)).sense(Sense::click()).fill(Color32::TRANSPARENT)).on_hover_text("tools of this operation").clicked() {
+                                central_window.push_last(central_window_enum, TableTree::new(data, WindowTable::PreOperativeToolReady(None), content.op_id));
+                            }
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(content.op_status.as_ref().map_or_else(|| "N/A".to_string(), |status| format!("{:?}", status))));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(highlight(ui, &content.room_name, &filter, TextField::Room)));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(format_date(content.start_time)));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(format_date(content.end_time)));
+                        });
+                    });
+                }
+            });
+        } else if let WindowTable::RoomProperty(Some(room)) = &window_table {
+            let occupancy = |count: usize, maximum: Option<i32>| match maximum {
+                Some(maximum) if count as i32 > maximum => RichText::new(format!("{} / {}", count, maximum)).color(Color32::from_rgb(255, 46, 32)),
+                Some(maximum) => RichText::new(format!("{} / {}", count, maximum)),
+                None => RichText::new(format!("{} / N/A", count)),
+            };
+            ui.heading(room.name.clone());
+            egui::Grid::new("room_property").num_columns(2).striped(true).show(ui, |ui| {
+                ui.label("purpose");
+                ui.label(room.purpose.as_ref().map_or_else(|| "N/A".to_string(), |purpose| format!("{:?}", purpose)));
+                ui.end_row();
+                ui.label("alias code");
+                ui.label(room.alias_code.clone());
+                ui.end_row();
+                ui.label("patients");
+                ui.label(occupancy(room.patients.len(), room.patient_maximum_occupancy)).on_hover_text(room.patients.join("\n"));
+                ui.end_row();
+                ui.label("staff");
+                ui.label(occupancy(room.staff.len(), room.staff_maximum_occupancy)).on_hover_text(room.staff.join("\n"));
+                ui.end_row();
+                ui.label("designated tools");
+                ui.vertical(|ui| {
+                    if room.tools.is_empty() {
+                        ui.label("none");
+                    }
+                    for tool in &room.tools {
+                        let status = tool.tool_status.as_ref().map_or_else(|| "N/A".to_string(), |status| status.to_string());
+                        if ui.link(format!("{} · {}", tool.equipment_name, status)).clicked() && tool.tool_id.is_some() {
+                            central_window.push_last(central_window_enum, TableTree::new(data, WindowTable::ToolDetail(None), tool.tool_id));
+                        }
+                    }
+                });
+                ui.end_row();
+            });
+            ui.separator();
+            ui.strong(format!("today's operations ({})", room.operations.len()));
+            TableBuilder::new(ui)
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .striped(true)
+            .header(20.0, |mut header| {
+                let headings = [
+                    "LABEL",
+                    "STATUS",
+                    "PATIENT FULL NAME",
+                    "STARTING OPERATION",
+                    "ENDING OPERATION",
+                ];
+                let sort = central_window.sort_mut(central_window_enum);
+                for title in headings {
+                    sort_header(&mut header, title, sort);
+                }
+            })
+            .body(|mut body| {
+                for content in &room.operations {
+                    let date_color = date_code(content.start_time, content.end_time);
+                    body.row(30.0, |mut row| {
+                        row.col(|ui| {
+                            if ui.add(Button::new(highlight(ui, &content.op_label, &filter, TextField::Label)).sense(Sense::click()).fill(Color32::TRANSPARENT)).on_hover_text("tools of this operation").clicked() {
+                                central_window.push_last(central_window_enum, TableTree::new(data, WindowTable::PreOperativeToolReady(None), content.op_id));
+                            }
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(content.op_status.as_ref().map_or_else(|| "N/A".to_string(), |status| format!("{:?}", status))));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(highlight(ui, &content.patient_full_name, &filter, TextField::Patient)));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(RichText::new(format_date(content.start_time)).color(date_color)));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(RichText::new(format_date(content.end_time)).color(date_color)));
+                        });
+                    });
+                }
+            });
+        } else if let WindowTable::ToolDetail(Some(tool)) = &window_table {
+            // opened from an operation's tools: that operation is already on screen one step back
+            let from_op_id = central_window.display_parent(central_window_enum)
+                .filter(|parent| matches!(parent.data, WindowTable::PreOperativeToolReady(_)))
+                .and_then(|parent| parent.id);
+            let operations: Vec<&ToolOperation> = tool.operations.iter()
+                .filter(|content| from_op_id.is_none() || content.op_id != from_op_id)
+                .collect();
+            ui.heading(tool.equipment_name.clone());
+            egui::Grid::new("tool_detail").num_columns(2).striped(true).show(ui, |ui| {
+                ui.label("status");
+                ui.label(tool.tool_status.as_ref().map_or_else(|| "N/A".to_string(), |status| status.to_string()));
+                ui.end_row();
+                ui.label("serial number");
+                ui.label(tool.serial_number.clone());
+                ui.end_row();
+                ui.label("manufacturer");
+                ui.label(tool.manufacturer.clone());
+                ui.end_row();
+                ui.label("brand");
+                ui.label(tool.brand.clone());
+                ui.end_row();
+                ui.label("designated room");
+                ui.vertical(|ui| {
+                    if tool.designated_rooms.is_empty() {
+                        ui.label("none");
+                    }
+                    for (room_id, name) in &tool.designated_rooms {
+                        if ui.link(name).clicked() && room_id.is_some() {
+                            central_window.push_last(central_window_enum, TableTree::new(data, WindowTable::RoomProperty(None), *room_id));
+                        }
+                    }
+                });
+                ui.end_row();
+                ui.label("inspectors");
+                ui.label(if tool.inspectors.is_empty() { "none".to_string() } else { tool.inspectors.join(", ") });
+                ui.end_row();
+            });
+            ui.separator();
+            if from_op_id.is_some() {
+                ui.strong(format!("other operations using it ({})", operations.len()));
+            } else {
+                ui.strong(format!("operations using it ({})", operations.len()));
+            }
+            TableBuilder::new(ui)
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .striped(true)
+            .header(20.0, |mut header| {
+                let headings = [
+                    "LABEL",
+                    "STATUS",
+                    "ROOM NAME",
+                    "ON SITE",
+                    "STARTING OPERATION",
+                    "ENDING OPERATION",
+                ];
+                let sort = central_window.sort_mut(central_window_enum);
+                for title in headings {
+                    sort_header(&mut header, title, sort);
+                }
+            })
+            .body(|mut body| {
+                for content in operations {
+                    let date_color = date_code(content.start_time, content.end_time);
+                    body.row(30.0, |mut row| {
+                        row.col(|ui| {
+                            if ui.add(Button::new(highlight(ui, &content.op_label, &filter, TextField::Label)).sense(Sense::click()).fill(Color32::TRANSPARENT)).on_hover_text("tools of this operation").clicked() {
+                                central_window.push_last(central_window_enum, TableTree::new(data, WindowTable::PreOperativeToolReady(None), content.op_id));
+                            }
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(content.op_status.as_ref().map_or_else(|| "N/A".to_string(), |status| format!("{:?}", status))));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(highlight(ui, &content.room_name, &filter, TextField::Room)));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(if content.on_site { "Yes" } else { "No" }));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(RichText::new(format_date(content.start_time)).color(date_color)));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(RichText::new(format_date(content.end_time)).color(date_color)));
+                        });
+                    });
+                }
+            });
+        } else if let WindowTable::OperationTeam(Some(team)) = &window_table {
+            ui.heading(format!("team of {}", team.op_label));
+            ui.horizontal(|ui| {
+                if team.missing_roles.is_empty() {
+                    ui.colored_label(Color32::from_rgb(0, 140, 26), "✔ every required role is assigned");
+                } else {
+                    let missing: Vec<String> = team.missing_roles.iter().map(|role| format!("{:?}", role)).collect();
+                    ui.colored_label(Color32::from_rgb(255, 46, 32), format!("⚠ missing {}", missing.join(", ")));
+                }
+                if ui.button("📣 request staff").on_hover_text("raise a staff request alert for this operation").clicked() {
+                    if let Some(op_id) = team.op_id {
+                        central_window.requests.push(DeskRequest::RaiseAlert { op_id, category: AlertCategory::StaffRequest });
+                    }
+                }
+            });
+            TableBuilder::new(ui)
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .column(Column::auto().resizable(false))
+            .striped(true)
+            .header(20.0, |mut header| {
+                let headings = ["FULL NAME", "ROLE", "PHONE", "EMAIL"];
+                let sort = central_window.sort_mut(central_window_enum);
+                for title in headings {
+                    sort_header(&mut header, title, sort);
+                }
+            })
+            .body(|mut body| {
+                for member in &team.members {
+                    body.row(30.0, |mut row| {
+                        row.col(|ui| {
+                            ui.add(Label::new(member.full_name.clone()));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(member.role.as_ref().map_or_else(|| "N/A".to_string(), |role| format!("{:?}", role))));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(member.phone.clone()));
+                        });
+                        row.col(|ui| {
+                            ui.add(Label::new(member.email.clone()));
+                        });
+                    });
+                }
+            });
+        } else if let WindowTable::CalendarVisualization(Some(lanes)) | WindowTable::StaffTimeline(Some(lanes)) = &window_table {
+            match calendar::calendar(ui, lanes, &mut central_window.calendar) {
+                Some(CalendarAction::Open(op_id)) => {
+                    central_window.push_last(central_window_enum, TableTree::new(data, WindowTable::PreOperativeToolReady(None), Some(op_id)));
+                },
+                Some(CalendarAction::Move { op_id, start_time, end_time, room_id }) => {
+                    central_window.requests.push(DeskRequest::Reschedule { op_id, start_time, end_time, room_id });
+                },
+                None => {},
+            }
         } else {
-            let tbl = TableBuilder::new(ui)
+            TableBuilder::new(ui)
             .column(Column::auto().resizable(false))
             .header(20.0, |mut header| {
                 header.col(|ui| {
@@ -141,8 +669,38 @@ impl BuildTable for TableData {
                     });
                 });
             });
-            tbl
-        };
-        table_return
+        }
     }
-}
\ No newline at end of file
+}
+
+/// `text` with the chars matched by `filter` marked, as a widget label.
+fn highlight(ui: &Ui, text: &str, filter: &Filter, field: TextField) -> LayoutJob {
+    let positions = highlighted(text, &filter.highlight_terms(Some(field)));
+    let font_id = TextStyle::Body.resolve(ui.style());
+    let plain = TextFormat { font_id: font_id.clone(), color: ui.visuals().text_color(), ..Default::default() };
+    let marked = TextFormat { font_id, color: Color32::BLACK, background: HIGHLIGHT, ..Default::default() };
+    let mut job = LayoutJob::default();
+    let mut run = String::new();
+    let mut run_marked = false;
+    for (position, c) in text.chars().enumerate() {
+        let is_marked = positions.binary_search(&position).is_ok();
+        if is_marked != run_marked && !run.is_empty() {
+            job.append(&run, 0.0, if run_marked { marked.clone() } else { plain.clone() });
+            run.clear();
+        }
+        run_marked = is_marked;
+        run.push(c);
+    }
+    job.append(&run, 0.0, if run_marked { marked } else { plain });
+    job
+}
+
+/// A heading that sorts by its column when clicked, flipping on the next click.
+fn sort_header(header: &mut TableRow, title: &'static str, sort: &mut Option<Sort>) {
+    header.col(|ui| {
+        let text = RichText::new(format!("{} {}", title, Sort::indicator(sort, title))).heading();
+        if ui.add(Button::new(text).fill(Color32::TRANSPARENT)).clicked() {
+            *sort = Some(Sort::toggle(sort, title));
+        }
+    });
+}
@@ -0,0 +1,382 @@
+use std::collections::{BTreeMap, HashSet};
+
+use chrono::NaiveDateTime;
+
+use crate::database::table::{EquipmentStatus, Operation, OperationStatus};
+
+use super::TableData;
+
+/// Two operations booked in the same room at overlapping times.
+#[derive(Debug, Clone)]
+pub struct RoomConflict {
+    pub room_id: i32,
+    pub first: i32,
+    pub second: i32,
+    pub overlap_start: NaiveDateTime,
+    pub overlap_end: NaiveDateTime,
+}
+
+/// One member of staff assigned to two operations at overlapping times.
+#[derive(Debug, Clone)]
+pub struct StaffConflict {
+    pub staff_id: i32,
+    pub first: i32,
+    pub second: i32,
+    pub overlap_start: NaiveDateTime,
+    pub overlap_end: NaiveDateTime,
+}
+
+/// A tool that cannot physically be where an operation needs it.
+#[derive(Debug, Clone)]
+pub enum ToolConflict {
+    /// Listed for two operations at overlapping times.
+    DoubleBooked {
+        tool_id: i32,
+        first: i32,
+        second: i32,
+        overlap_start: NaiveDateTime,
+        overlap_end: NaiveDateTime,
+    },
+    /// Lent out while an operation that has not happened yet needs it.
+    Borrowed {
+        tool_id: i32,
+        op_id: i32,
+    },
+}
+impl ToolConflict {
+    pub fn tool_id(&self) -> i32 {
+        match self {
+            ToolConflict::DoubleBooked { tool_id, .. } | ToolConflict::Borrowed { tool_id, .. } => *tool_id,
+        }
+    }
+    fn involves(&self, op: i32) -> bool {
+        match self {
+            ToolConflict::DoubleBooked { first, second, .. } => *first == op || *second == op,
+            ToolConflict::Borrowed { op_id, .. } => *op_id == op,
+        }
+    }
+}
+
+/// Scheduling problems found across the operation tables.
+#[derive(Debug, Clone, Default)]
+pub struct ConflictReport {
+    pub rooms: Vec<RoomConflict>,
+    pub staff: Vec<StaffConflict>,
+    pub tools: Vec<ToolConflict>,
+}
+impl ConflictReport {
+    /// Checks the current rows as of `now`.
+    pub fn check(data: &TableData, now: NaiveDateTime) -> Self {
+        let mut tools = tool_double_bookings(data, now);
+        tools.extend(borrowed_tools(data, now));
+        ConflictReport {
+            rooms: double_bookings(data, now),
+            staff: double_assignments(data, now),
+            tools,
+        }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.rooms.is_empty() && self.staff.is_empty() && self.tools.is_empty()
+    }
+    pub fn len(&self) -> usize {
+        self.rooms.len() + self.staff.len() + self.tools.len()
+    }
+    /// Problems with `tool_id` for the operation `op_id`.
+    pub fn tool_conflicts_of(&self, op_id: Option<i32>, tool_id: Option<i32>) -> Vec<&ToolConflict> {
+        let (Some(op_id), Some(tool_id)) = (op_id, tool_id) else {
+            return Vec::new();
+        };
+        self.tools.iter()
+            .filter(|conflict| conflict.tool_id() == tool_id && conflict.involves(op_id))
+            .collect()
+    }
+    /// Operations sharing a room with `op_id` at the same time.
+    pub fn room_conflicts_of(&self, op_id: Option<i32>) -> Vec<i32> {
+        self.rooms.iter()
+            .filter_map(|conflict| match op_id {
+                Some(id) if conflict.first == id => Some(conflict.second),
+                Some(id) if conflict.second == id => Some(conflict.first),
+                _ => None,
+            })
+            .collect()
+    }
+    /// `(staff_id, other op_id)` for staff of `op_id` busy elsewhere at the same time.
+    pub fn staff_conflicts_of(&self, op_id: Option<i32>) -> Vec<(i32, i32)> {
+        self.staff.iter()
+            .filter_map(|conflict| match op_id {
+                Some(id) if conflict.first == id => Some((conflict.staff_id, conflict.second)),
+                Some(id) if conflict.second == id => Some((conflict.staff_id, conflict.first)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+type Booking = (i32, NaiveDateTime, NaiveDateTime);
+
+/// Scheduled or running, and not over by `now`. Only these can still clash.
+fn is_active(op: &Operation, now: NaiveDateTime) -> bool {
+    matches!(op.status, Some(OperationStatus::PreOperative) | Some(OperationStatus::InProgress))
+        && op.end_time.is_none_or(|end_time| end_time > now)
+}
+
+/// `(start_time, end_time)` of `op` if it is active and fully scheduled.
+fn active_times(op: &Operation, now: NaiveDateTime) -> Option<(NaiveDateTime, NaiveDateTime)> {
+    match (op.start_time, op.end_time) {
+        (Some(start_time), Some(end_time)) if is_active(op, now) => Some((start_time, end_time)),
+        _ => None,
+    }
+}
+
+/// `(key, first, second, overlap_start, overlap_end)` for every pair of
+/// bookings under the same key that share time.
+fn overlapping_pairs(by_key: BTreeMap<i32, Vec<Booking>>) -> Vec<(i32, i32, i32, NaiveDateTime, NaiveDateTime)> {
+    let mut pairs = Vec::new();
+    for (key, mut bookings) in by_key {
+        // an operation linked twice (e.g. two rows for one staff) is still one booking
+        let mut seen = HashSet::new();
+        bookings.retain(|(id, _, _)| seen.insert(*id));
+        bookings.sort_by_key(|(_, start_time, _)| *start_time);
+        for (position, (first, first_start, first_end)) in bookings.iter().enumerate() {
+            // sorted by start, so only later bookings starting before this one ends can overlap
+            for (second, second_start, second_end) in bookings[position + 1..].iter().take_while(|(_, start_time, _)| start_time < first_end) {
+                if overlaps(*first_start, *first_end, *second_start, *second_end) {
+                    pairs.push((key, *first, *second, *second_start, (*first_end).min(*second_end)));
+                }
+            }
+        }
+    }
+    pairs
+}
+
+fn double_bookings(data: &TableData, now: NaiveDateTime) -> Vec<RoomConflict> {
+    let operations = data.operation.read();
+    let mut by_room: BTreeMap<i32, Vec<Booking>> = BTreeMap::new();
+    for op in operations.iter() {
+        if let (Some(id), Some(room_id), Some((start_time, end_time))) = (op.id, op.room_id, active_times(op, now)) {
+            by_room.entry(room_id).or_default().push((id, start_time, end_time));
+        }
+    }
+    overlapping_pairs(by_room).into_iter()
+        .map(|(room_id, first, second, overlap_start, overlap_end)| RoomConflict { room_id, first, second, overlap_start, overlap_end })
+        .collect()
+}
+
+fn double_assignments(data: &TableData, now: NaiveDateTime) -> Vec<StaffConflict> {
+    let operations = data.operation.read();
+    let operation_staff = data.operation_staff.read();
+    let mut by_staff: BTreeMap<i32, Vec<Booking>> = BTreeMap::new();
+    for op_staff in operation_staff.iter() {
+        let (Some(staff_id), Some(op_id)) = (op_staff.staff_id, op_staff.operation_id) else {
+            continue;
+        };
+        if let Some((start_time, end_time)) = operations.get(op_id).and_then(|op| active_times(op, now)) {
+            by_staff.entry(staff_id).or_default().push((op_id, start_time, end_time));
+        }
+    }
+    overlapping_pairs(by_staff).into_iter()
+        .map(|(staff_id, first, second, overlap_start, overlap_end)| StaffConflict { staff_id, first, second, overlap_start, overlap_end })
+        .collect()
+}
+
+fn tool_double_bookings(data: &TableData, now: NaiveDateTime) -> Vec<ToolConflict> {
+    let operations = data.operation.read();
+    let operation_tools = data.operation_tool.read();
+    let mut by_tool: BTreeMap<i32, Vec<Booking>> = BTreeMap::new();
+    for op_tool in operation_tools.iter() {
+        let (Some(tool_id), Some(op_id)) = (op_tool.tool_id, op_tool.operation_id) else {
+            continue;
+        };
+        if let Some((start_time, end_time)) = operations.get(op_id).and_then(|op| active_times(op, now)) {
+            by_tool.entry(tool_id).or_default().push((op_id, start_time, end_time));
+        }
+    }
+    overlapping_pairs(by_tool).into_iter()
+        .map(|(tool_id, first, second, overlap_start, overlap_end)| ToolConflict::DoubleBooked { tool_id, first, second, overlap_start, overlap_end })
+        .collect()
+}
+
+/// Tools assigned to operations still to come that are lent out.
+/// `tool_reservation` has no tool column, so reservations cannot be
+/// tied to a tool and are not part of this check.
+fn borrowed_tools(data: &TableData, now: NaiveDateTime) -> Vec<ToolConflict> {
+    let operations = data.operation.read();
+    let operation_tools = data.operation_tool.read();
+    let tools = data.tool.read();
+    let mut conflicts = Vec::new();
+    for op_tool in operation_tools.iter() {
+        let (Some(tool_id), Some(op)) = (op_tool.tool_id, op_tool.operation_id.and_then(|id| operations.get(id))) else {
+            continue;
+        };
+        let Some(op_id) = op.id else {
+            continue;
+        };
+        if !is_active(op, now) {
+            continue;
+        }
+        if tools.get(tool_id).is_some_and(|tool| tool.status == Some(EquipmentStatus::Borrowed)) {
+            conflicts.push(ToolConflict::Borrowed { tool_id, op_id });
+        }
+    }
+    conflicts
+}
+
+/// Whether `[a_start, a_end)` and `[b_start, b_end)` share any time.
+pub fn overlaps(a_start: NaiveDateTime, a_end: NaiveDateTime, b_start: NaiveDateTime, b_end: NaiveDateTime) -> bool {
+    a_start < b_end && b_start < a_end
+}
+
+/// Active operations other than `op_id` booked in `room_id` during the given time.
+pub fn room_conflicts(data: &TableData, op_id: i32, room_id: i32, start_time: NaiveDateTime, end_time: NaiveDateTime, now: NaiveDateTime) -> Vec<i32> {
+    data.operation.read().iter()
+        .filter(|op| op.id.is_some_and(|id| id != op_id) && op.room_id == Some(room_id))
+        .filter(|op| match active_times(op, now) {
+            Some((other_start, other_end)) => overlaps(start_time, end_time, other_start, other_end),
+            None => false,
+        })
+        .filter_map(|op| op.id)
+        .collect()
+}
+
+/// `(staff_id, other op_id)` for every member of `op_id`'s staff already
+/// assigned to another active operation during the given time.
+pub fn staff_conflicts(data: &TableData, op_id: i32, start_time: NaiveDateTime, end_time: NaiveDateTime, now: NaiveDateTime) -> Vec<(i32, i32)> {
+    let operations = data.operation.read();
+    let operation_staff = data.operation_staff.read();
+    let staff_ids: Vec<i32> = operation_staff.iter()
+        .filter(|op_staff| op_staff.operation_id == Some(op_id))
+        .filter_map(|op_staff| op_staff.staff_id)
+        .collect();
+    operation_staff.iter()
+        .filter(|op_staff| op_staff.operation_id.is_some_and(|id| id != op_id))
+        .filter_map(|op_staff| Some((op_staff.staff_id?, op_staff.operation_id?)))
+        .filter(|(staff_id, _)| staff_ids.contains(staff_id))
+        .filter(|(_, other_id)| match operations.get(*other_id).and_then(|op| active_times(op, now)) {
+            Some((other_start, other_end)) => overlaps(start_time, end_time, other_start, other_end),
+            None => false,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use crate::database::table::{OperationStaff, OperationTool, Tool};
+
+    use super::*;
+
+    fn at(hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, 1).unwrap().and_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    fn operation(id: i32, status: OperationStatus, start_hour: u32, end_hour: u32) -> Operation {
+        Operation {
+            id: Some(id),
+            status: Some(status),
+            room_id: Some(1),
+            start_time: Some(at(start_hour)),
+            end_time: Some(at(end_hour)),
+            ..Operation::default()
+        }
+    }
+
+    #[test]
+    fn overlapping_bookings_are_paired_with_their_shared_time() {
+        let by_key = BTreeMap::from([(7, vec![(2, at(10), at(12)), (1, at(9), at(11)), (3, at(12), at(13))])]);
+        let pairs = overlapping_pairs(by_key);
+        assert_eq!(pairs, vec![(7, 1, 2, at(10), at(11))]);
+    }
+
+    #[test]
+    fn back_to_back_and_other_keys_do_not_overlap() {
+        let by_key = BTreeMap::from([
+            (1, vec![(1, at(9), at(10)), (2, at(10), at(11))]),
+            (2, vec![(3, at(9), at(11))]),
+        ]);
+        assert!(overlapping_pairs(by_key).is_empty());
+    }
+
+    #[test]
+    fn the_same_operation_is_not_paired_with_itself() {
+        let by_key = BTreeMap::from([(1, vec![(1, at(9), at(11)), (1, at(9), at(11))])]);
+        assert!(overlapping_pairs(by_key).is_empty());
+    }
+
+    #[test]
+    fn a_duplicate_separated_by_another_booking_is_still_one_booking() {
+        let by_key = BTreeMap::from([(1, vec![(1, at(9), at(11)), (2, at(9), at(12)), (1, at(9), at(11))])]);
+        assert_eq!(overlapping_pairs(by_key), vec![(1, 1, 2, at(9), at(11))]);
+    }
+
+    #[test]
+    fn only_active_operations_clash() {
+        let data = TableData::new();
+        data.operation.update(1, operation(1, OperationStatus::PreOperative, 14, 16));
+        data.operation.update(2, operation(2, OperationStatus::InProgress, 15, 17));
+        data.operation.update(3, operation(3, OperationStatus::PostOperative, 14, 16));
+        data.operation.update(4, operation(4, OperationStatus::PreOperative, 8, 15));
+        let conflicts = double_bookings(&data, at(15));
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!((conflicts[0].first, conflicts[0].second), (1, 2));
+    }
+
+    fn assign_tool(data: &TableData, id: i32, op_id: i32, tool_id: i32) {
+        data.operation_tool.update(id, OperationTool { id: Some(id), operation_id: Some(op_id), tool_id: Some(tool_id), ..OperationTool::default() });
+    }
+
+    #[test]
+    fn a_tool_listed_for_two_overlapping_operations_is_double_booked() {
+        let data = TableData::new();
+        data.operation.update(1, operation(1, OperationStatus::PreOperative, 14, 16));
+        data.operation.update(2, Operation { room_id: Some(2), ..operation(2, OperationStatus::PreOperative, 15, 17) });
+        data.operation.update(3, Operation { room_id: Some(3), ..operation(3, OperationStatus::PreOperative, 16, 18) });
+        assign_tool(&data, 1, 1, 5);
+        assign_tool(&data, 2, 2, 5);
+        assign_tool(&data, 3, 3, 6);
+        let conflicts = tool_double_bookings(&data, at(12));
+        assert_eq!(conflicts.len(), 1);
+        assert!(matches!(conflicts[0], ToolConflict::DoubleBooked { tool_id: 5, first: 1, second: 2, .. }));
+    }
+
+    #[test]
+    fn a_tool_of_a_finished_operation_is_not_double_booked() {
+        let data = TableData::new();
+        data.operation.update(1, operation(1, OperationStatus::PostOperative, 14, 16));
+        data.operation.update(2, operation(2, OperationStatus::PreOperative, 15, 17));
+        assign_tool(&data, 1, 1, 5);
+        assign_tool(&data, 2, 2, 5);
+        assert!(tool_double_bookings(&data, at(12)).is_empty());
+    }
+
+    #[test]
+    fn a_borrowed_tool_is_flagged_for_operations_still_to_come() {
+        let data = TableData::new();
+        data.tool.update(5, Tool { id: Some(5), status: Some(EquipmentStatus::Borrowed), ..Tool::default() });
+        data.tool.update(6, Tool { id: Some(6), status: Some(EquipmentStatus::Ready), ..Tool::default() });
+        data.operation.update(1, operation(1, OperationStatus::PreOperative, 14, 16));
+        data.operation.update(2, operation(2, OperationStatus::PreOperative, 8, 10));
+        assign_tool(&data, 1, 1, 5);
+        assign_tool(&data, 2, 2, 5);
+        assign_tool(&data, 3, 1, 6);
+        let conflicts = borrowed_tools(&data, at(12));
+        assert_eq!(conflicts.len(), 1);
+        assert!(matches!(conflicts[0], ToolConflict::Borrowed { tool_id: 5, op_id: 1 }));
+        assert_eq!(ConflictReport::check(&data, at(12)).tool_conflicts_of(Some(1), Some(5)).len(), 1);
+    }
+
+    #[test]
+    fn a_reschedule_only_clashes_with_active_operations() {
+        let data = TableData::new();
+        data.operation.update(1, operation(1, OperationStatus::PreOperative, 18, 19));
+        data.operation.update(2, operation(2, OperationStatus::PostOperative, 14, 16));
+        data.operation.update(3, operation(3, OperationStatus::PreOperative, 15, 17));
+        data.operation.update(4, operation(4, OperationStatus::PreOperative, 10, 12));
+        for op_id in 1..=4 {
+            data.operation_staff.update(op_id, OperationStaff { id: Some(op_id), operation_id: Some(op_id), staff_id: Some(7) });
+        }
+        // moving operation 1 to 11:00-16:00 at noon: 2 is finished and 4 has just ended
+        assert_eq!(room_conflicts(&data, 1, 1, at(11), at(16), at(12)), vec![3]);
+        assert_eq!(staff_conflicts(&data, 1, at(11), at(16), at(12)), vec![(7, 3)]);
+    }
+}
@@ -0,0 +1,133 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+use super::TableData;
+
+#[derive(Debug, Clone)]
+pub struct FieldChange {
+    pub field: String,
+    pub old: Value,
+    pub new: Value,
+}
+
+#[derive(Debug, Clone)]
+pub struct RowChange {
+    pub id: i64,
+    pub fields: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TableDiff {
+    pub table: &'static str,
+    pub added: Vec<Value>,
+    pub removed: Vec<Value>,
+    pub changed: Vec<RowChange>,
+}
+impl TableDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Rows of every table as JSON, taken before a snapshot replaces them.
+pub struct Capture(Vec<(&'static str, Vec<Value>)>);
+
+/// What a re-`Initialize` changed, table by table. Rows are matched by id.
+#[derive(Debug, Clone)]
+pub struct SnapshotDiff {
+    pub received_at: DateTime<Utc>,
+    pub tables: Vec<TableDiff>,
+}
+impl SnapshotDiff {
+    pub fn capture(data: &TableData) -> Capture {
+        Capture(data.tables().iter().map(|table| (table.name(), table.rows_json())).collect())
+    }
+    pub fn between(before: Capture, data: &TableData) -> Self {
+        let tables = before.0.into_iter()
+            .zip(data.tables())
+            .map(|((name, old_rows), table)| diff_rows(name, old_rows, table.rows_json()))
+            .filter(|table_diff| !table_diff.is_empty())
+            .collect();
+        SnapshotDiff { received_at: Utc::now(), tables }
+    }
+    pub fn is_empty(&self) -> bool {
+        self.tables.is_empty()
+    }
+}
+
+fn by_id(rows: Vec<Value>) -> BTreeMap<i64, Value> {
+    rows.into_iter()
+        .filter_map(|row| row.get("id").and_then(Value::as_i64).map(|id| (id, row)))
+        .collect()
+}
+
+fn diff_rows(table: &'static str, old_rows: Vec<Value>, new_rows: Vec<Value>) -> TableDiff {
+    let mut old_rows = by_id(old_rows);
+    let mut table_diff = TableDiff { table, ..TableDiff::default() };
+    for (id, new_row) in by_id(new_rows) {
+        match old_rows.remove(&id) {
+            None => table_diff.added.push(new_row),
+            Some(old_row) if old_row != new_row => {
+                let empty = serde_json::Map::new();
+                let old_fields = old_row.as_object().unwrap_or(&empty);
+                let new_fields = new_row.as_object().unwrap_or(&empty);
+                let mut fields: Vec<&String> = old_fields.keys().chain(new_fields.keys()).collect();
+                fields.sort();
+                fields.dedup();
+                let fields = fields.into_iter()
+                    .filter_map(|field| {
+                        let old = old_fields.get(field).cloned().unwrap_or(Value::Null);
+                        let new = new_fields.get(field).cloned().unwrap_or(Value::Null);
+                        (old != new).then(|| FieldChange { field: field.clone(), old, new })
+                    })
+                    .collect();
+                table_diff.changed.push(RowChange { id, fields });
+            },
+            Some(_) => {},
+        }
+    }
+    table_diff.removed = old_rows.into_values().collect();
+    table_diff
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn rows_are_matched_by_id() {
+        let old_rows = vec![json!({ "id": 1, "label": "APP" }), json!({ "id": 2, "label": "CHOLE" })];
+        let new_rows = vec![json!({ "id": 2, "label": "CHOLE" }), json!({ "id": 3, "label": "HERNIA" })];
+        let table_diff = diff_rows("operation", old_rows, new_rows);
+        assert_eq!(table_diff.added, vec![json!({ "id": 3, "label": "HERNIA" })]);
+        assert_eq!(table_diff.removed, vec![json!({ "id": 1, "label": "APP" })]);
+        assert!(table_diff.changed.is_empty());
+    }
+
+    #[test]
+    fn changed_rows_list_only_the_fields_that_differ() {
+        let old_rows = vec![json!({ "id": 1, "label": "APP", "room_id": 4, "status": "PreOperative" })];
+        let new_rows = vec![json!({ "id": 1, "label": "APP", "room_id": 5 })];
+        let table_diff = diff_rows("operation", old_rows, new_rows);
+        assert_eq!(table_diff.changed.len(), 1);
+        let change = &table_diff.changed[0];
+        assert_eq!(change.id, 1);
+        let fields: Vec<(&str, &Value, &Value)> = change.fields.iter()
+            .map(|field| (field.field.as_str(), &field.old, &field.new))
+            .collect();
+        assert_eq!(fields, vec![
+            ("room_id", &json!(4), &json!(5)),
+            ("status", &json!("PreOperative"), &Value::Null),
+        ]);
+    }
+
+    #[test]
+    fn identical_tables_and_rows_without_id_give_no_diff() {
+        let rows = vec![json!({ "id": 1, "label": "APP" }), json!({ "label": "no id" })];
+        assert!(diff_rows("operation", rows.clone(), rows).is_empty());
+    }
+}
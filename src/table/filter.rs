@@ -0,0 +1,458 @@
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+
+use crate::local_now;
+
+use super::{query_return::*, search::{fuzzy_match, Searchable}};
+
+/// A comparison in `ready<50%` style terms.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Comparison {
+    Less,
+    LessOrEqual,
+    Equal,
+    GreaterOrEqual,
+    Greater,
+}
+impl Comparison {
+    fn holds(self, left: f64, right: f64) -> bool {
+        match self {
+            Comparison::Less => left < right,
+            Comparison::LessOrEqual => left <= right,
+            Comparison::Equal => (left - right).abs() < f64::EPSILON,
+            Comparison::GreaterOrEqual => left >= right,
+            Comparison::Greater => left > right,
+        }
+    }
+}
+
+/// Text fields a `key:value` term can be aimed at.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TextField {
+    Label,
+    Patient,
+    Room,
+    Equipment,
+}
+
+/// One term of a search, typed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Predicate {
+    /// A bare word, fuzzy matched against every searchable field.
+    Text(String),
+    Field(TextField, String),
+    Status(String),
+    OnSite(bool),
+    /// Percentage of tools on site, `0.0..=100.0`.
+    Ready(Comparison, f64),
+    Starts(NaiveDate),
+    Ends(NaiveDate),
+}
+
+/// Fields of a row the typed predicates read. `None` means the row has no
+/// such field, and predicates over it are skipped for that view.
+pub trait Filterable: Searchable {
+    fn text_field(&self, _field: TextField) -> Option<&str> {
+        None
+    }
+    /// Status in lower case without separators, e.g. `forinspection`.
+    fn status(&self) -> Option<String> {
+        None
+    }
+    fn on_site(&self) -> Option<bool> {
+        None
+    }
+    fn ready(&self) -> Option<f64> {
+        None
+    }
+    fn start_time(&self) -> Option<Option<NaiveDateTime>> {
+        None
+    }
+    fn end_time(&self) -> Option<Option<NaiveDateTime>> {
+        None
+    }
+}
+
+fn normalize(text: &str) -> String {
+    text.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+impl Filterable for PreOperativeDefault {
+    fn text_field(&self, field: TextField) -> Option<&str> {
+        match field {
+            TextField::Label => Some(&self.op_label),
+            TextField::Patient => Some(&self.patient_full_name),
+            TextField::Room => Some(&self.room_name),
+            TextField::Equipment => None,
+        }
+    }
+    fn status(&self) -> Option<String> {
+        Some(normalize(&format!("{:?}", self.op_status)))
+    }
+    fn ready(&self) -> Option<f64> {
+        Some(self.on_site_percentage)
+    }
+    fn start_time(&self) -> Option<Option<NaiveDateTime>> {
+        Some(self.start_time)
+    }
+    fn end_time(&self) -> Option<Option<NaiveDateTime>> {
+        Some(self.end_time)
+    }
+}
+impl Filterable for PreOperativeToolReady {
+    fn text_field(&self, field: TextField) -> Option<&str> {
+        (field == TextField::Equipment).then_some(self.equipment_name.as_str())
+    }
+    fn status(&self) -> Option<String> {
+        Some(normalize(&self.tool_status.to_string()))
+    }
+    fn on_site(&self) -> Option<bool> {
+        Some(self.on_site)
+    }
+}
+impl Filterable for InProgressDefault {
+    fn text_field(&self, field: TextField) -> Option<&str> {
+        match field {
+            TextField::Label => Some(&self.op_label),
+            TextField::Patient => Some(&self.patient_full_name),
+            TextField::Room => Some(&self.room_name),
+            TextField::Equipment => None,
+        }
+    }
+    fn start_time(&self) -> Option<Option<NaiveDateTime>> {
+        Some(self.start_time)
+    }
+    fn end_time(&self) -> Option<Option<NaiveDateTime>> {
+        Some(self.end_time)
+    }
+}
+impl Filterable for PostOperativeDefault {
+    fn text_field(&self, field: TextField) -> Option<&str> {
+        match field {
+            TextField::Label => Some(&self.op_label),
+            TextField::Patient => Some(&self.patient_full_name),
+            TextField::Room => Some(&self.room_name),
+            TextField::Equipment => None,
+        }
+    }
+    fn start_time(&self) -> Option<Option<NaiveDateTime>> {
+        Some(self.start_time)
+    }
+    fn end_time(&self) -> Option<Option<NaiveDateTime>> {
+        Some(self.end_time)
+    }
+}
+impl Filterable for RecoveryDefault {
+    fn text_field(&self, field: TextField) -> Option<&str> {
+        match field {
+            TextField::Label => Some(&self.op_label),
+            TextField::Patient => Some(&self.patient_full_name),
+            TextField::Room => Some(&self.ward_room_name),
+            TextField::Equipment => None,
+        }
+    }
+    fn end_time(&self) -> Option<Option<NaiveDateTime>> {
+        Some(self.end_time)
+    }
+}
+impl Filterable for DischargeDefault {
+    fn text_field(&self, field: TextField) -> Option<&str> {
+        match field {
+            TextField::Label => Some(&self.op_label),
+            TextField::Patient => Some(&self.patient_full_name),
+            TextField::Room => Some(&self.room_name),
+            TextField::Equipment => None,
+        }
+    }
+    fn end_time(&self) -> Option<Option<NaiveDateTime>> {
+        Some(self.end_time)
+    }
+}
+impl Filterable for PatientOperation {
+    fn text_field(&self, field: TextField) -> Option<&str> {
+        match field {
+            TextField::Label => Some(&self.op_label),
+            TextField::Room => Some(&self.room_name),
+            TextField::Patient | TextField::Equipment => None,
+        }
+    }
+    fn status(&self) -> Option<String> {
+        Some(self.op_status.as_ref().map(|status| normalize(&format!("{:?}", status))).unwrap_or_default())
+    }
+    fn start_time(&self) -> Option<Option<NaiveDateTime>> {
+        Some(self.start_time)
+    }
+    fn end_time(&self) -> Option<Option<NaiveDateTime>> {
+        Some(self.end_time)
+    }
+}
+impl Filterable for ToolOperation {
+    fn text_field(&self, field: TextField) -> Option<&str> {
+        match field {
+            TextField::Label => Some(&self.op_label),
+            TextField::Room => Some(&self.room_name),
+            TextField::Patient | TextField::Equipment => None,
+        }
+    }
+    fn status(&self) -> Option<String> {
+        Some(self.op_status.as_ref().map(|status| normalize(&format!("{:?}", status))).unwrap_or_default())
+    }
+    fn on_site(&self) -> Option<bool> {
+        Some(self.on_site)
+    }
+    fn start_time(&self) -> Option<Option<NaiveDateTime>> {
+        Some(self.start_time)
+    }
+    fn end_time(&self) -> Option<Option<NaiveDateTime>> {
+        Some(self.end_time)
+    }
+}
+impl Filterable for RoomOperation {
+    fn text_field(&self, field: TextField) -> Option<&str> {
+        match field {
+            TextField::Label => Some(&self.op_label),
+            TextField::Patient => Some(&self.patient_full_name),
+            TextField::Room | TextField::Equipment => None,
+        }
+    }
+    fn status(&self) -> Option<String> {
+        Some(self.op_status.as_ref().map(|status| normalize(&format!("{:?}", status))).unwrap_or_default())
+    }
+    fn start_time(&self) -> Option<Option<NaiveDateTime>> {
+        Some(self.start_time)
+    }
+    fn end_time(&self) -> Option<Option<NaiveDateTime>> {
+        Some(self.end_time)
+    }
+}
+impl Filterable for TeamMember {}
+impl Filterable for CalendarBlock {
+    fn text_field(&self, field: TextField) -> Option<&str> {
+        match field {
+            TextField::Label => Some(&self.op_label),
+            TextField::Patient => Some(&self.patient_full_name),
+            TextField::Room => Some(&self.room_name),
+            TextField::Equipment => None,
+        }
+    }
+    fn status(&self) -> Option<String> {
+        Some(self.op_status.as_ref().map(|status| normalize(&format!("{:?}", status))).unwrap_or_default())
+    }
+    fn ready(&self) -> Option<f64> {
+        Some(self.on_site_percentage)
+    }
+    fn start_time(&self) -> Option<Option<NaiveDateTime>> {
+        Some(self.start_time)
+    }
+    fn end_time(&self) -> Option<Option<NaiveDateTime>> {
+        Some(self.end_time)
+    }
+}
+
+impl Predicate {
+    /// `None` when the row has nothing this predicate talks about.
+    fn test(&self, row: &impl Filterable) -> Option<bool> {
+        let on_day = |time: Option<NaiveDateTime>, day: &NaiveDate| time.is_some_and(|time| time.date() == *day);
+        match self {
+            Predicate::Text(term) => Some(row.search_fields().iter().any(|field| fuzzy_match(field, term).is_some())),
+            Predicate::Field(field, term) => row.text_field(*field).map(|text| fuzzy_match(text, term).is_some()),
+            Predicate::Status(status) => row.status().map(|row_status| row_status.starts_with(status.as_str())),
+            Predicate::OnSite(on_site) => row.on_site().map(|row_on_site| row_on_site == *on_site),
+            Predicate::Ready(comparison, percentage) => row.ready().map(|ready| comparison.holds(ready, *percentage)),
+            Predicate::Starts(day) => row.start_time().map(|time| on_day(time, day)),
+            Predicate::Ends(day) => row.end_time().map(|time| on_day(time, day)),
+        }
+    }
+}
+
+/// A parsed search box, e.g. `room:OR-2 status:borrowed ready<50% starts:today`.
+#[derive(Debug, Clone, Default)]
+pub struct Filter {
+    pub predicates: Vec<Predicate>,
+    /// Terms that could not be parsed; they are left out of the filter.
+    pub errors: Vec<String>,
+}
+impl Filter {
+    pub fn parse(input: &str) -> Self {
+        let mut filter = Filter::default();
+        for term in tokens(input) {
+            match parse_term(&term) {
+                Ok(predicate) => filter.predicates.push(predicate),
+                Err(err) => filter.errors.push(format!("{}: {}", term, err)),
+            }
+        }
+        filter
+    }
+    pub fn is_empty(&self) -> bool {
+        self.predicates.is_empty()
+    }
+    /// Every predicate that applies to the row has to hold.
+    pub fn matches(&self, row: &impl Filterable) -> bool {
+        self.predicates.iter().all(|predicate| predicate.test(row).unwrap_or(true))
+    }
+    /// Terms whose matches are worth highlighting in `field`.
+    pub fn highlight_terms(&self, field: Option<TextField>) -> Vec<&str> {
+        self.predicates.iter()
+            .filter_map(|predicate| match predicate {
+                Predicate::Text(term) => Some(term.as_str()),
+                Predicate::Field(predicate_field, term) if Some(*predicate_field) == field => Some(term.as_str()),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+/// Splits on whitespace, keeping `"double quoted"` runs together.
+fn tokens(input: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut quoted = false;
+    for c in input.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            },
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+fn parse_term(term: &str) -> Result<Predicate, String> {
+    if let Some(rest) = term.strip_prefix("ready").filter(|rest| rest.starts_with(['<', '>', '=', ':'])) {
+        let (comparison, number) = [
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+            ("=", Comparison::Equal),
+            (":", Comparison::Equal),
+        ]
+            .into_iter()
+            .find_map(|(operator, comparison)| rest.strip_prefix(operator).map(|number| (comparison, number)))
+            .ok_or("expected <, <=, =, >= or > after ready")?;
+        let percentage: f64 = number.trim_end_matches('%').parse().map_err(|_| "expected a percentage")?;
+        return Ok(Predicate::Ready(comparison, percentage));
+    }
+    let Some((key, value)) = term.split_once(':') else {
+        return Ok(Predicate::Text(term.to_string()));
+    };
+    if value.is_empty() {
+        return Err("missing value".to_string());
+    }
+    match key.to_lowercase().as_str() {
+        "label" => Ok(Predicate::Field(TextField::Label, value.to_string())),
+        "patient" => Ok(Predicate::Field(TextField::Patient, value.to_string())),
+        "room" => Ok(Predicate::Field(TextField::Room, value.to_string())),
+        "equipment" | "tool" => Ok(Predicate::Field(TextField::Equipment, value.to_string())),
+        "status" => Ok(Predicate::Status(normalize(value))),
+        "onsite" => match value.to_lowercase().as_str() {
+            "yes" | "true" | "1" => Ok(Predicate::OnSite(true)),
+            "no" | "false" | "0" => Ok(Predicate::OnSite(false)),
+            _ => Err("expected yes or no".to_string()),
+        },
+        "starts" => parse_day(value).map(Predicate::Starts),
+        "ends" => parse_day(value).map(Predicate::Ends),
+        _ => Err(format!("unknown key {}", key)),
+    }
+}
+
+fn parse_day(value: &str) -> Result<NaiveDate, String> {
+    let today = local_now().date();
+    match value.to_lowercase().as_str() {
+        "today" => Ok(today),
+        "tomorrow" => Ok(today + Duration::days(1)),
+        "yesterday" => Ok(today - Duration::days(1)),
+        _ => NaiveDate::parse_from_str(value, "%Y-%m-%d").map_err(|_| "expected today, tomorrow, yesterday or YYYY-MM-DD".to_string()),
+    }
+}
+
+/// Syntax reference shown by the "help" button, as (example, meaning).
+pub const SYNTAX: &[(&str, &str)] = &[
+    ("jua", "any word is fuzzy matched against label, patient, room and equipment"),
+    ("\"juan dela\"", "double quotes keep spaces inside one term"),
+    ("label:APP", "operation label"),
+    ("patient:cruz", "patient full name"),
+    ("room:OR-2", "room name"),
+    ("equipment:scalpel", "equipment name, tool views only"),
+    ("status:borrowed", "operation or tool status, prefix is enough"),
+    ("onsite:no", "tool on site, yes or no"),
+    ("ready<50%", "tools on site; <, <=, =, >=, > and the % is optional"),
+    ("starts:today", "start day; today, tomorrow, yesterday or YYYY-MM-DD"),
+    ("ends:2024-05-01", "end day, same values as starts"),
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bare_words_and_quoted_runs_are_text() {
+        let filter = Filter::parse("jua  \"dela cruz\"");
+        assert_eq!(filter.predicates, vec![Predicate::Text("jua".to_string()), Predicate::Text("dela cruz".to_string())]);
+        assert!(filter.errors.is_empty());
+    }
+
+    #[test]
+    fn keys_pick_their_field() {
+        let filter = Filter::parse("label:APP Room:OR-2 tool:scalpel status:For_Inspection onsite:no");
+        assert_eq!(filter.predicates, vec![
+            Predicate::Field(TextField::Label, "APP".to_string()),
+            Predicate::Field(TextField::Room, "OR-2".to_string()),
+            Predicate::Field(TextField::Equipment, "scalpel".to_string()),
+            Predicate::Status("forinspection".to_string()),
+            Predicate::OnSite(false),
+        ]);
+    }
+
+    #[test]
+    fn ready_takes_every_comparison() {
+        let filter = Filter::parse("ready<50% ready<=50 ready=50 ready:50% ready>=50 ready>50");
+        let comparisons: Vec<Comparison> = filter.predicates.iter()
+            .map(|predicate| match predicate {
+                Predicate::Ready(comparison, percentage) => {
+                    assert_eq!(*percentage, 50.0);
+                    *comparison
+                },
+                other => panic!("expected a ready predicate, got {:?}", other),
+            })
+            .collect();
+        assert_eq!(comparisons, vec![
+            Comparison::Less,
+            Comparison::LessOrEqual,
+            Comparison::Equal,
+            Comparison::Equal,
+            Comparison::GreaterOrEqual,
+            Comparison::Greater,
+        ]);
+    }
+
+    #[test]
+    fn days_are_relative_or_iso() {
+        let today = local_now().date();
+        let filter = Filter::parse("starts:today ends:tomorrow starts:2024-05-01");
+        assert_eq!(filter.predicates, vec![
+            Predicate::Starts(today),
+            Predicate::Ends(today + Duration::days(1)),
+            Predicate::Starts(NaiveDate::from_ymd_opt(2024, 5, 1).unwrap()),
+        ]);
+    }
+
+    #[test]
+    fn bad_terms_are_reported_and_left_out() {
+        let filter = Filter::parse("colour:red onsite:maybe ready>half label: starts:soon cruz");
+        assert_eq!(filter.predicates, vec![Predicate::Text("cruz".to_string())]);
+        assert_eq!(filter.errors.len(), 5);
+        assert!(filter.errors[0].starts_with("colour:red: unknown key"));
+    }
+
+    #[test]
+    fn readiness_is_not_taken_for_a_key() {
+        assert_eq!(Filter::parse("readiness").predicates, vec![Predicate::Text("readiness".to_string())]);
+    }
+}
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use crate::TableTarget;
+
+use super::TableData;
+
+/// A row pointing at an id that does not exist in the target table.
+#[derive(Debug, Clone)]
+pub struct DanglingKey {
+    pub table: &'static str,
+    pub row_id: Option<i32>,
+    pub column: &'static str,
+    pub target: TableTarget,
+    pub missing_id: i32,
+}
+
+#[derive(Debug, Clone)]
+pub struct DuplicateId {
+    pub table: &'static str,
+    pub id: i32,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct MissingId {
+    pub table: &'static str,
+    pub row: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IntegrityReport {
+    pub dangling: Vec<DanglingKey>,
+    pub duplicates: Vec<DuplicateId>,
+    pub missing_ids: Vec<MissingId>,
+    by_reference: HashMap<(TableTarget, i32, &'static str), usize>,
+}
+impl IntegrityReport {
+    pub fn check(data: &TableData) -> Self {
+        let mut report = IntegrityReport::default();
+        for table in data.tables() {
+            let mut seen: HashMap<i32, usize> = HashMap::new();
+            for (row, (row_id, foreign_keys)) in table.keys().into_iter().enumerate() {
+                match row_id {
+                    Some(id) => *seen.entry(id).or_default() += 1,
+                    None => report.missing_ids.push(MissingId { table: table.name(), row }),
+                }
+                for foreign_key in foreign_keys {
+                    let Some(id) = foreign_key.id else {
+                        continue;
+                    };
+                    let exists = data.table(foreign_key.target).is_some_and(|target| target.contains(id));
+                    if exists {
+                        continue;
+                    }
+                    if let Some(row_id) = row_id {
+                        report.by_reference.insert((table.target(), row_id, foreign_key.column), report.dangling.len());
+                    }
+                    report.dangling.push(DanglingKey {
+                        table: table.name(),
+                        row_id,
+                        column: foreign_key.column,
+                        target: foreign_key.target,
+                        missing_id: id,
+                    });
+                }
+            }
+            let mut duplicates: Vec<DuplicateId> = seen.into_iter()
+                .filter(|(_, count)| *count > 1)
+                .map(|(id, count)| DuplicateId { table: table.name(), id, count })
+                .collect();
+            duplicates.sort_by_key(|duplicate| duplicate.id);
+            report.duplicates.extend(duplicates);
+        }
+        report
+    }
+    pub fn is_clean(&self) -> bool {
+        self.dangling.is_empty() && self.duplicates.is_empty() && self.missing_ids.is_empty()
+    }
+    pub fn dangling(&self, table: TableTarget, row_id: Option<i32>, column: &'static str) -> Option<&DanglingKey> {
+        self.by_reference.get(&(table, row_id?, column))
+            .map(|position| &self.dangling[*position])
+    }
+    /// What a query shows in place of a reference that did not resolve.
+    pub fn missing_label(&self, table: TableTarget, row_id: Option<i32>, column: &'static str) -> String {
+        match self.dangling(table, row_id, column) {
+            Some(dangling) => format!("⚠ missing {:?} #{}", dangling.target, dangling.missing_id),
+            None => "N/A".to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::table::{Operation, Patient, Room};
+
+    use super::*;
+
+    fn operation(id: i32, patient_id: i32, room_id: i32) -> Operation {
+        Operation { id: Some(id), patient_id: Some(patient_id), room_id: Some(room_id), ..Operation::default() }
+    }
+
+    fn data_with_patient_and_room() -> TableData {
+        let data = TableData::new();
+        data.patient.update(1, Patient { id: Some(1), ..Patient::default() });
+        data.room.update(1, Room { id: Some(1), ..Room::default() });
+        data
+    }
+
+    #[test]
+    fn resolved_keys_are_clean() {
+        let data = data_with_patient_and_room();
+        data.operation.update(1, operation(1, 1, 1));
+        let report = IntegrityReport::check(&data);
+        assert!(report.is_clean());
+        assert_eq!(report.missing_label(TableTarget::Operation, Some(1), "room_id"), "N/A");
+    }
+
+    #[test]
+    fn dangling_keys_are_reported_by_row_and_column() {
+        let data = data_with_patient_and_room();
+        data.operation.update(1, operation(1, 1, 9));
+        let report = IntegrityReport::check(&data);
+        assert!(!report.is_clean());
+        assert_eq!(report.dangling.len(), 1);
+        let dangling = report.dangling(TableTarget::Operation, Some(1), "room_id").unwrap();
+        assert_eq!((dangling.table, dangling.target, dangling.missing_id), ("operation", TableTarget::Room, 9));
+        assert!(report.dangling(TableTarget::Operation, Some(1), "patient_id").is_none());
+        assert_eq!(report.missing_label(TableTarget::Operation, Some(1), "room_id"), "⚠ missing Room #9");
+    }
+
+    #[test]
+    fn empty_keys_are_not_dangling() {
+        let data = data_with_patient_and_room();
+        data.operation.update(1, Operation { id: Some(1), ..Operation::default() });
+        assert!(IntegrityReport::check(&data).is_clean());
+    }
+
+    #[test]
+    fn duplicate_and_missing_ids_are_reported() {
+        let data = TableData::new();
+        data.room.initialize(vec![
+            Room { id: Some(1), ..Room::default() },
+            Room { id: Some(1), ..Room::default() },
+            Room::default(),
+        ]);
+        let report = IntegrityReport::check(&data);
+        assert_eq!(report.duplicates.len(), 1);
+        assert_eq!((report.duplicates[0].table, report.duplicates[0].id, report.duplicates[0].count), ("room", 1, 2));
+        assert_eq!(report.missing_ids.len(), 1);
+        assert_eq!((report.missing_ids[0].table, report.missing_ids[0].row), ("room", 2));
+    }
+}
@@ -0,0 +1,160 @@
+use rusqlite::{limits::Limit, types::Value, Connection};
+
+use super::{TableData, SECRET_COLUMNS};
+
+/// Upper bound on rows kept from one console query.
+const MAX_ROWS: usize = 1000;
+
+/// In-memory SQLite copy of every `TableData` table, for ad-hoc reporting.
+/// Each table is rebuilt whenever its version moves.
+pub struct SqlMirror {
+    connection: Connection,
+    versions: Vec<Option<u64>>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct QueryResult {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    pub truncated: bool,
+}
+
+impl SqlMirror {
+    pub fn open() -> rusqlite::Result<Self> {
+        let connection = Connection::open_in_memory()?;
+        // ATTACH counts as read-only but creates the file it attaches
+        connection.set_limit(Limit::SQLITE_LIMIT_ATTACHED, 0);
+        Ok(SqlMirror {
+            connection,
+            versions: Vec::new(),
+        })
+    }
+    pub fn sync(&mut self, data: &TableData) -> rusqlite::Result<()> {
+        let tables = data.tables();
+        self.versions.resize(tables.len(), None);
+        for (table, synced_version) in tables.into_iter().zip(self.versions.iter_mut()) {
+            let version = table.version();
+            if *synced_version == Some(version) {
+                continue;
+            }
+            let mut columns = table.columns();
+            columns.retain(|column| !SECRET_COLUMNS.contains(&(table.name(), column.as_str())));
+            let quoted_columns: Vec<String> = columns.iter().map(|column| format!("\"{}\"", column)).collect();
+            let transaction = self.connection.transaction()?;
+            transaction.execute_batch(&format!(
+                "DROP TABLE IF EXISTS \"{name}\"; CREATE TABLE \"{name}\" ({columns});",
+                name = table.name(),
+                columns = quoted_columns.join(", "),
+            ))?;
+            {
+                let placeholders = vec!["?"; columns.len()].join(", ");
+                let mut insert = transaction.prepare(&format!(
+                    "INSERT INTO \"{}\" ({}) VALUES ({})",
+                    table.name(),
+                    quoted_columns.join(", "),
+                    placeholders,
+                ))?;
+                for row in table.rows_json() {
+                    let values = columns.iter().map(|column| to_sql_value(row.get(column)));
+                    insert.execute(rusqlite::params_from_iter(values))?;
+                }
+            }
+            transaction.commit()?;
+            *synced_version = Some(version);
+        }
+        Ok(())
+    }
+    /// Runs a single read-only statement and renders every cell as text.
+    pub fn query(&self, sql: &str) -> Result<QueryResult, String> {
+        let mut statement = self.connection.prepare(sql).map_err(|err| err.to_string())?;
+        if !statement.readonly() {
+            return Err("only read-only statements are allowed".to_string());
+        }
+        let columns: Vec<String> = statement.column_names().into_iter().map(str::to_string).collect();
+        let mut rows = statement.query([]).map_err(|err| err.to_string())?;
+        let mut result = QueryResult { columns, ..QueryResult::default() };
+        while let Some(row) = rows.next().map_err(|err| err.to_string())? {
+            if result.rows.len() == MAX_ROWS {
+                result.truncated = true;
+                break;
+            }
+            let cells = (0..result.columns.len())
+                .map(|position| match row.get::<_, Value>(position) {
+                    Ok(Value::Null) => "NULL".to_string(),
+                    Ok(Value::Integer(value)) => value.to_string(),
+                    Ok(Value::Real(value)) => value.to_string(),
+                    Ok(Value::Text(value)) => value,
+                    Ok(Value::Blob(value)) => format!("<{} bytes>", value.len()),
+                    Err(err) => err.to_string(),
+                })
+                .collect();
+            result.rows.push(cells);
+        }
+        Ok(result)
+    }
+}
+
+fn to_sql_value(value: Option<&serde_json::Value>) -> Value {
+    match value {
+        None | Some(serde_json::Value::Null) => Value::Null,
+        Some(serde_json::Value::Bool(value)) => Value::Integer(*value as i64),
+        Some(serde_json::Value::Number(number)) => match number.as_i64() {
+            Some(integer) => Value::Integer(integer),
+            None => Value::Real(number.as_f64().unwrap_or_default()),
+        },
+        Some(serde_json::Value::String(text)) => Value::Text(text.clone()),
+        Some(other) => Value::Text(other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::database::table::Frontdesk;
+
+    use super::*;
+
+    fn mirror_of(data: &TableData) -> SqlMirror {
+        let mut mirror = SqlMirror::open().unwrap();
+        mirror.sync(data).unwrap();
+        mirror
+    }
+
+    #[test]
+    fn secrets_are_not_mirrored() {
+        let data = TableData::new();
+        data.frontdesk.update(1, Frontdesk {
+            id: Some(1),
+            name: Some("desk".to_string()),
+            password: Some("hunter2".to_string()),
+            session_token: Some("token".to_string()),
+            ..Frontdesk::default()
+        });
+        let mirror = mirror_of(&data);
+        let result = mirror.query("select * from frontdesk").unwrap();
+        assert!(result.columns.contains(&"name".to_string()));
+        assert!(!result.columns.contains(&"password".to_string()));
+        assert!(!result.columns.contains(&"session_token".to_string()));
+        assert!(mirror.query("select password from frontdesk").is_err());
+    }
+
+    #[test]
+    fn only_read_only_statements_run() {
+        let mirror = mirror_of(&TableData::new());
+        assert!(mirror.query("select count(*) from operation").is_ok());
+        assert!(mirror.query("delete from operation").is_err());
+    }
+
+    #[test]
+    fn attach_is_refused_however_it_is_written() {
+        let mirror = mirror_of(&TableData::new());
+        let path = std::env::temp_dir().join(format!("frontdesk_mirror_attach_{}.db", std::process::id()));
+        for sql in [
+            format!("attach database '{}' as copy", path.display()),
+            format!("/* x */ ATTACH DATABASE '{}' AS copy", path.display()),
+            format!("-- x\n  attach '{}' as copy", path.display()),
+        ] {
+            assert!(mirror.query(&sql).is_err(), "{}", sql);
+            assert!(!path.exists(), "{}", sql);
+        }
+    }
+}
@@ -1,121 +1,172 @@
-use std::sync::{Arc, Mutex, RwLock};
-use query_return::{PreOperativeDefault, PreOperativeToolReady, WindowTable};
-use serde::{Deserialize, Serialize};
-use serde_json::json;
-use update::UpdateEquipmentRow;
+use std::sync::{Arc, PoisonError, RwLock};
 
-use egui_extras::{TableBuilder, Column};
+use chrono::{NaiveDateTime, Timelike};
+use serde::{Deserialize, Serialize};
 
 mod update;
 pub mod build;
+pub mod conflict;
+pub mod diff;
+pub mod filter;
+pub mod integrity;
+#[cfg(feature = "sql-mirror")]
+pub mod mirror;
+pub mod pending;
 pub mod query;
 pub mod query_return;
+pub mod search;
+pub mod snapshot;
+pub mod sort;
+pub mod store;
 
 pub use build::BuildTable;
+pub use conflict::ConflictReport;
+pub use integrity::IntegrityReport;
 pub use query::Query;
+pub use snapshot::{LoadReport, SnapshotError, StoredSnapshot};
+pub use store::{AnyTable, Entity, Table};
 
-use crate::{database::{self, table::{self, *}}, date_code, format_date, window::{self, *}, TableTarget};
+use crate::{database::table::*, local_now, TableTarget};
 
-#[derive(Deserialize, Debug, Serialize)]
-#[serde(rename_all = "lowercase")]
-pub enum TableRow {
-    Equipment(Equipment), 
-    Room(Room),           
-    Tool(Tool),           
-    Staff(Staff),         
-    ToolReservation(ToolReservation), 
-    ToolDesignatedRoom(ToolDesignatedRoom),
-    ToolInspector(ToolInspector),  
-    Patient(Patient),              
-    Operation(Operation),          
-    PatientWardRoom(PatientWardRoom), 
-    PatientWardAssistant(PatientWardAssistant), 
-    OperationStaff(OperationStaff),  
-    OperationTool(OperationTool), 
-}
+/// `(table, column)` pairs that never leave memory: not mirrored, not stored.
+pub(crate) const SECRET_COLUMNS: &[(&str, &str)] = &[("frontdesk", "password"), ("frontdesk", "session_token")];
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
-pub struct RawTable {
-    pub equipment: Vec<Equipment>,
-    pub room: Vec<Room>,
-    pub tool: Vec<Tool>,
-    pub staff: Vec<Staff>,                          
-    pub tool_reservation: Vec<ToolReservation>,     
-    pub tool_designated_room: Vec<ToolDesignatedRoom>, 
-    pub tool_inspector: Vec<ToolInspector>,        
-    pub patient: Vec<Patient>,                     
-    pub operation: Vec<Operation>,                 
-    pub patient_ward_room: Vec<PatientWardRoom>,   
-    pub patient_ward_assistant: Vec<PatientWardAssistant>, 
-    pub operation_staff: Vec<OperationStaff>,       
-    pub operation_tool: Vec<OperationTool>,
-    pub alert: Vec<Alert>,
-    pub frontdesk: Vec<OperationStaff>,
-    pub alert_frontdesk: Vec<OperationStaff>,
-    pub alert_staff: Vec<OperationStaff>   
-}
-#[derive(Debug, Clone)]
+type CheckedIntegrity = (Vec<u64>, Arc<IntegrityReport>);
+/// Versions of every table and the minute a conflict check ran at.
+pub type ConflictsKey = (Vec<u64>, NaiveDateTime);
+type CheckedConflicts = (ConflictsKey, Arc<ConflictReport>);
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
 pub struct TableData {
-    pub equipment: Arc<RwLock<Vec<database::table::Equipment>>>,
-    pub room: Arc<RwLock<Vec<database::table::Room>>>,
-    pub tool: Arc<RwLock<Vec<database::table::Tool>>>,
-    pub staff: Arc<RwLock<Vec<database::table::Staff>>>,
-    pub tool_reservation: Arc<RwLock<Vec<database::table::ToolReservation>>>,
-    pub tool_designated_room: Arc<RwLock<Vec<database::table::ToolDesignatedRoom>>>,
-    pub tool_inspector: Arc<RwLock<Vec<database::table::ToolInspector>>>,
-    pub patient: Arc<RwLock<Vec<database::table::Patient>>>,
-    pub operation: Arc<RwLock<Vec<database::table::Operation>>>,
-    pub patient_ward_room: Arc<RwLock<Vec<database::table::PatientWardRoom>>>,
-    pub patient_ward_assistant: Arc<RwLock<Vec<database::table::PatientWardAssistant>>>,
-    pub operation_staff: Arc<RwLock<Vec<database::table::OperationStaff>>>,
-    pub operation_tool: Arc<RwLock<Vec<database::table::OperationTool>>>,
+    pub equipment: Table<Equipment>,
+    pub room: Table<Room>,
+    pub tool: Table<Tool>,
+    pub staff: Table<Staff>,
+    pub tool_reservation: Table<ToolReservation>,
+    pub tool_designated_room: Table<ToolDesignatedRoom>,
+    pub tool_inspector: Table<ToolInspector>,
+    pub patient: Table<Patient>,
+    pub operation: Table<Operation>,
+    pub patient_ward_room: Table<PatientWardRoom>,
+    pub patient_ward_assistant: Table<PatientWardAssistant>,
+    pub operation_staff: Table<OperationStaff>,
+    pub operation_tool: Table<OperationTool>,
+    pub alert: Table<Alert>,
+    pub frontdesk: Table<Frontdesk>,
+    pub alert_frontdesk: Table<AlertFrontdesk>,
+    pub alert_staff: Table<AlertStaff>,
+    /// Last integrity check and the table versions it was run against.
+    #[serde(skip)]
+    integrity: Arc<RwLock<Option<CheckedIntegrity>>>,
+    /// Last conflict check and the table versions it was run against.
+    #[serde(skip)]
+    conflicts: Arc<RwLock<Option<CheckedConflicts>>>,
 }
 impl TableData {
     pub fn new() -> Self {
-        TableData {
-            equipment: Arc::new(RwLock::new(Vec::new())),
-            room: Arc::new(RwLock::new(Vec::new())),
-            tool: Arc::new(RwLock::new(Vec::new())),
-            staff: Arc::new(RwLock::new(Vec::new())),
-            tool_reservation: Arc::new(RwLock::new(Vec::new())),
-            tool_designated_room: Arc::new(RwLock::new(Vec::new())),
-            tool_inspector: Arc::new(RwLock::new(Vec::new())),
-            patient: Arc::new(RwLock::new(Vec::new())),
-            operation: Arc::new(RwLock::new(Vec::new())),
-            patient_ward_room: Arc::new(RwLock::new(Vec::new())),
-            patient_ward_assistant: Arc::new(RwLock::new(Vec::new())),
-            operation_staff: Arc::new(RwLock::new(Vec::new())),
-            operation_tool: Arc::new(RwLock::new(Vec::new())),
+        TableData::default()
+    }
+    pub fn tables(&self) -> [&dyn AnyTable; 17] {
+        [
+            &self.equipment,
+            &self.room,
+            &self.tool,
+            &self.staff,
+            &self.tool_reservation,
+            &self.tool_designated_room,
+            &self.tool_inspector,
+            &self.patient,
+            &self.operation,
+            &self.patient_ward_room,
+            &self.patient_ward_assistant,
+            &self.operation_staff,
+            &self.operation_tool,
+            &self.alert,
+            &self.frontdesk,
+            &self.alert_frontdesk,
+            &self.alert_staff,
+        ]
+    }
+    pub fn table(&self, target: TableTarget) -> Option<&dyn AnyTable> {
+        self.tables().into_iter().find(|table| table.target() == target)
+    }
+    /// Integrity of the current rows, re-checked whenever any table changed.
+    pub fn integrity(&self) -> Arc<IntegrityReport> {
+        let versions: Vec<u64> = self.tables().iter().map(|table| table.version()).collect();
+        let mut cached = self.integrity.write().unwrap_or_else(PoisonError::into_inner);
+        if let Some((checked_versions, report)) = cached.as_ref() {
+            if *checked_versions == versions {
+                return Arc::clone(report);
+            }
+        }
+        let report = Arc::new(IntegrityReport::check(self));
+        *cached = Some((versions, Arc::clone(&report)));
+        report
+    }
+    /// Scheduling conflicts of the current rows, re-checked whenever any table
+    /// changed or the minute turned over.
+    pub fn conflicts(&self) -> Arc<ConflictReport> {
+        let now = local_now();
+        let key = self.conflicts_key_at(now);
+        let mut cached = self.conflicts.write().unwrap_or_else(PoisonError::into_inner);
+        if let Some((checked_key, report)) = cached.as_ref() {
+            if *checked_key == key {
+                return Arc::clone(report);
+            }
         }
+        let report = Arc::new(ConflictReport::check(self, now));
+        *cached = Some((key, Arc::clone(&report)));
+        report
     }
-    pub fn initialize(&mut self, raw_string: String) {
-        let raw_table: RawTable = serde_json::from_str(&raw_string).expect("parse error");
-        self.equipment = Arc::new(RwLock::new(raw_table.equipment.clone()));
-        self.room = Arc::new(RwLock::new(raw_table.room.clone()));
-        self.tool = Arc::new(RwLock::new(raw_table.tool.clone()));
-        self.staff = Arc::new(RwLock::new(raw_table.staff.clone()));
-        self.tool_reservation = Arc::new(RwLock::new(raw_table.tool_reservation.clone()));
-        self.tool_designated_room = Arc::new(RwLock::new(raw_table.tool_designated_room.clone()));
-        self.tool_inspector = Arc::new(RwLock::new(raw_table.tool_inspector.clone()));
-        self.patient = Arc::new(RwLock::new(raw_table.patient.clone()));
-        self.operation = Arc::new(RwLock::new(raw_table.operation.clone()));
-        self.patient_ward_room = Arc::new(RwLock::new(raw_table.patient_ward_room.clone()));
-        self.patient_ward_assistant = Arc::new(RwLock::new(raw_table.patient_ward_assistant.clone()));
-        self.operation_staff = Arc::new(RwLock::new(raw_table.operation_staff.clone()));
-        self.operation_tool = Arc::new(RwLock::new(raw_table.operation_tool.clone()));
+    /// What `conflicts()` is cached on right now.
+    pub fn conflicts_key(&self) -> ConflictsKey {
+        self.conflicts_key_at(local_now())
     }
-    pub fn update(&self, raw_string: String, database_table: TableTarget) {
-        match serde_json::from_str::<UpdateEquipmentRow>(&raw_string) {
-            Ok(update_table_data) => {
-                let mut rows = self.equipment.write().unwrap();
-                //if let Some(row) = rows.iter_mut().find(|r| r.id.unwrap() == update_table_data.id as i32) {
-                //    *row = update_table_data.new_row_data;
-                //} else {
-                //}
-            },
-            Err(_) => todo!(),
+    fn conflicts_key_at(&self, now: NaiveDateTime) -> ConflictsKey {
+        let versions: Vec<u64> = self.tables().iter().map(|table| table.version()).collect();
+        // finished operations drop out of the check, so it also goes stale every minute
+        let minute = now.with_second(0).and_then(|now| now.with_nanosecond(0)).unwrap_or(now);
+        (versions, minute)
+    }
+    /// Current version of each table in `targets`, in the same order.
+    pub fn versions(&self, targets: &[TableTarget]) -> Vec<u64> {
+        targets.iter()
+            .map(|target| self.table(*target).map_or(0, |table| table.version()))
+            .collect()
+    }
+    /// Replaces every table with the snapshot in `raw_string`. The snapshot is
+    /// loaded on the side first, so nothing changes if it cannot be used.
+    pub fn initialize(&self, raw_string: String) -> Result<LoadReport, SnapshotError> {
+        let mut raw_table: serde_json::Map<String, serde_json::Value> = serde_json::from_str(&raw_string)
+            .map_err(SnapshotError::Malformed)?;
+        let staged = TableData::new();
+        let mut report = LoadReport::default();
+        for table in staged.tables() {
+            let rows = raw_table.remove(table.name()).unwrap_or_else(|| serde_json::Value::Array(Vec::new()));
+            table.initialize_json(rows, &mut report)?;
+        }
+        for (table, staged_table) in self.tables().into_iter().zip(staged.tables()) {
+            table.adopt(staged_table);
+        }
+        Ok(report)
+    }
+    pub fn update(&self, raw_string: String, database_table: TableTarget) -> Result<(), serde_json::Error> {
+        if let Some(table) = self.table(database_table) {
+            table.update_json(&raw_string)?;
         }
+        Ok(())
+    }
+    pub fn insert(&self, raw_string: String, database_table: TableTarget) -> Result<(), serde_json::Error> {
+        if let Some(table) = self.table(database_table) {
+            table.insert_json(&raw_string)?;
+        }
+        Ok(())
+    }
+    pub fn delete(&self, raw_string: String, database_table: TableTarget) -> Result<(), serde_json::Error> {
+        if let Some(table) = self.table(database_table) {
+            table.delete_json(&raw_string)?;
+        }
+        Ok(())
     }
 }
-
- 
\ No newline at end of file
@@ -0,0 +1,207 @@
+use chrono::{DateTime, Duration, Utc};
+use serde_json::{json, Value};
+
+use crate::{ReceiveMessage, TableTarget};
+
+use super::{update::UpdateRow, Entity, Table, TableData};
+
+/// How long the server gets to answer an update before it is rolled back.
+const ANSWER_TIMEOUT: i64 = 15;
+
+/// An update applied locally before the server confirmed it.
+#[derive(Debug, Clone)]
+pub struct PendingUpdate {
+    pub table: TableTarget,
+    pub id: i32,
+    /// What the desk is told if the update has to be undone.
+    pub description: String,
+    /// Last row the server confirmed, or the row before the first edit.
+    previous: Value,
+    sent_at: DateTime<Utc>,
+    /// Updates of this row sent and not answered yet.
+    unanswered: usize,
+}
+
+/// Updates sent to the server and shown optimistically until it answers.
+#[derive(Debug, Clone, Default)]
+pub struct PendingUpdates {
+    updates: Vec<PendingUpdate>,
+}
+impl PendingUpdates {
+    /// Writes `row` into `table` right away and returns the `data` of the
+    /// update to send. The row it replaced is kept to roll back to, unless
+    /// the row already waits for an answer: then the confirmed row is kept.
+    pub fn apply<T: Entity>(&mut self, table: &Table<T>, id: i32, row: T, description: String) -> Result<String, serde_json::Error> {
+        let raw_string = serde_json::to_string(&UpdateRow { id: id as u32, new_row_data: row.clone() })?;
+        if let Some(update) = self.updates.iter_mut().find(|update| update.table == T::TARGET && update.id == id) {
+            update.description = description;
+            update.sent_at = Utc::now();
+            update.unanswered += 1;
+        } else {
+            self.updates.push(PendingUpdate {
+                table: T::TARGET,
+                id,
+                description,
+                previous: serde_json::to_value(table.read().get(id))?,
+                sent_at: Utc::now(),
+                unanswered: 1,
+            });
+        }
+        table.update(id, row);
+        Ok(raw_string)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.updates.is_empty()
+    }
+    /// Settles the pending updates an `Update` answer is about. A rejection
+    /// puts the last confirmed row back and returns what was undone.
+    pub fn resolve(&mut self, data: &TableData, message: &ReceiveMessage) -> Vec<PendingUpdate> {
+        let answer = serde_json::from_str::<Value>(&message.data).ok();
+        let id = answer.as_ref()
+            .and_then(|value| value.get("id").and_then(Value::as_i64))
+            .map(|id| id as i32);
+        let (answered, waiting): (Vec<PendingUpdate>, Vec<PendingUpdate>) = std::mem::take(&mut self.updates)
+            .into_iter()
+            .partition(|update| update.table == message.table_name && id.is_none_or(|id| update.id == id));
+        self.updates = waiting;
+        if message.is_rejected() {
+            for update in &answered {
+                update.roll_back(data);
+            }
+            return answered;
+        }
+        // later edits of the row are still on their way; roll those back to what was just confirmed
+        let confirmed = answer.as_ref().and_then(|value| value.get("new_row_data")).cloned();
+        for mut update in answered.into_iter().filter(|update| update.unanswered > 1) {
+            update.unanswered -= 1;
+            if let Some(confirmed) = &confirmed {
+                update.previous = confirmed.clone();
+            }
+            self.updates.push(update);
+        }
+        Vec::new()
+    }
+    /// Rolls back the updates the server never answered.
+    pub fn expire(&mut self, data: &TableData) -> Vec<PendingUpdate> {
+        let deadline = Utc::now() - Duration::seconds(ANSWER_TIMEOUT);
+        let (expired, waiting): (Vec<PendingUpdate>, Vec<PendingUpdate>) = std::mem::take(&mut self.updates)
+            .into_iter()
+            .partition(|update| update.sent_at < deadline);
+        self.updates = waiting;
+        for update in &expired {
+            update.roll_back(data);
+        }
+        expired
+    }
+}
+impl PendingUpdate {
+    fn roll_back(&self, data: &TableData) {
+        let Some(table) = data.table(self.table) else {
+            return;
+        };
+        let result = if self.previous.is_null() {
+            table.delete_json(&json!({ "id": self.id }).to_string())
+        } else {
+            table.update_json(&json!({ "id": self.id, "new_row_data": self.previous }).to_string())
+        };
+        if let Err(err) = result {
+            println!("err rolling back {:?} #{}: {}", self.table, self.id, err);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{database::table::Operation, ws::receive::Operation as MessageOperation};
+
+    use super::*;
+
+    fn operation(label: &str) -> Operation {
+        Operation { id: Some(1), label: Some(label.to_string()), ..Operation::default() }
+    }
+
+    fn answer(status_code: &str, data: Value) -> ReceiveMessage {
+        ReceiveMessage {
+            table_name: TableTarget::Operation,
+            operation: MessageOperation::Update,
+            status_code: status_code.to_string(),
+            data: data.to_string(),
+        }
+    }
+
+    fn label(data: &TableData) -> Option<String> {
+        data.operation.read().get(1).and_then(|op| op.label.clone())
+    }
+
+    #[test]
+    fn apply_writes_the_row_right_away() {
+        let data = TableData::new();
+        data.operation.update(1, operation("original"));
+        let mut pending = PendingUpdates::default();
+        pending.apply(&data.operation, 1, operation("first"), "first".to_string()).unwrap();
+        assert_eq!(label(&data).as_deref(), Some("first"));
+        assert!(!pending.is_empty());
+    }
+
+    #[test]
+    fn rejection_rolls_back_to_the_original() {
+        let data = TableData::new();
+        data.operation.update(1, operation("original"));
+        let mut pending = PendingUpdates::default();
+        pending.apply(&data.operation, 1, operation("first"), "first".to_string()).unwrap();
+        let undone = pending.resolve(&data, &answer("400", json!({ "id": 1 })));
+        assert_eq!(undone.len(), 1);
+        assert_eq!(label(&data).as_deref(), Some("original"));
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn two_edits_then_rejection_roll_back_to_the_original() {
+        let data = TableData::new();
+        data.operation.update(1, operation("original"));
+        let mut pending = PendingUpdates::default();
+        pending.apply(&data.operation, 1, operation("first"), "first".to_string()).unwrap();
+        pending.apply(&data.operation, 1, operation("second"), "second".to_string()).unwrap();
+        let undone = pending.resolve(&data, &answer("409", json!({ "id": 1 })));
+        assert_eq!(undone.len(), 1);
+        assert_eq!(undone[0].description, "second");
+        assert_eq!(label(&data).as_deref(), Some("original"));
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn confirmed_first_edit_becomes_the_rollback_of_the_second() {
+        let data = TableData::new();
+        data.operation.update(1, operation("original"));
+        let mut pending = PendingUpdates::default();
+        pending.apply(&data.operation, 1, operation("first"), "first".to_string()).unwrap();
+        pending.apply(&data.operation, 1, operation("second"), "second".to_string()).unwrap();
+        let accepted = json!({ "id": 1, "new_row_data": serde_json::to_value(operation("first")).unwrap() });
+        assert!(pending.resolve(&data, &answer("200", accepted)).is_empty());
+        assert!(!pending.is_empty());
+        pending.resolve(&data, &answer("400", json!({ "id": 1 })));
+        assert_eq!(label(&data).as_deref(), Some("first"));
+        assert!(pending.is_empty());
+    }
+
+    #[test]
+    fn new_row_is_deleted_on_rejection() {
+        let data = TableData::new();
+        let mut pending = PendingUpdates::default();
+        pending.apply(&data.operation, 1, operation("new"), "new".to_string()).unwrap();
+        pending.resolve(&data, &answer("500", json!({ "id": 1 })));
+        assert!(data.operation.read().get(1).is_none());
+    }
+
+    #[test]
+    fn expire_keeps_fresh_updates() {
+        let data = TableData::new();
+        data.operation.update(1, operation("original"));
+        let mut pending = PendingUpdates::default();
+        pending.apply(&data.operation, 1, operation("first"), "first".to_string()).unwrap();
+        assert!(pending.expire(&data).is_empty());
+        pending.updates[0].sent_at = Utc::now() - Duration::seconds(ANSWER_TIMEOUT + 1);
+        assert_eq!(pending.expire(&data).len(), 1);
+        assert_eq!(label(&data).as_deref(), Some("original"));
+    }
+}
@@ -1,4 +1,40 @@
-use super::{query_return::{PreOperativeDefault, PreOperativeToolReady, WindowTable}, EquipmentStatus, OperationStatus, TableData};
+use crate::{database::table::{EquipmentStatus, Operation, OperationStatus, OperationTool, Patient, PatientWardRoom, Room, Staff, StaffRole}, local_now, TableTarget};
+
+use super::{conflict::{ConflictReport, ToolConflict}, store::Rows, query_return::{CalendarBlock, CalendarLane, DischargeDefault, InProgressDefault, PatientData, PatientOperation, PostOperativeDefault, OperationTeam, PreOperativeDefault, PreOperativeToolReady, Readiness, RecoveryDefault, REQUIRED_ROLES, RoomOperation, RoomProperty, RoomTool, TeamMember, ToolDetail, ToolOperation, WardAssistant, WindowTable}, TableData};
+
+/// A patient keeps one ward room; the latest assignment wins.
+fn current_ward_room(ward_rooms: &[PatientWardRoom], patient_id: Option<i32>) -> Option<&PatientWardRoom> {
+    ward_rooms.iter().rfind(|ward_room| patient_id.is_some() && ward_room.patient_id == patient_id)
+}
+
+/// "name (other operation)" for each member of `op_id`'s staff busy elsewhere at the same time.
+fn staff_conflict_labels(conflicts: &ConflictReport, op_id: Option<i32>, staff: &Rows<Staff>, operations: &Rows<Operation>) -> Vec<String> {
+    conflicts.staff_conflicts_of(op_id).into_iter()
+        .map(|(staff_id, other_id)| format!(
+            "{} ({})",
+            staff.get(staff_id)
+                .map(|s| s.full_name())
+                .unwrap_or_else(|| format!("staff #{}", staff_id)),
+            operations.get(other_id).and_then(|op| op.label.clone()).unwrap_or_else(|| format!("#{}", other_id)),
+        ))
+        .collect()
+}
+
+/// What keeps a tool from `op_id`, worded for the desk.
+fn tool_conflict_label(conflict: &ToolConflict, op_id: i32, operations: &Rows<Operation>) -> String {
+    match conflict {
+        ToolConflict::DoubleBooked { first, second, overlap_start, overlap_end, .. } => {
+            let other = if *first == op_id { *second } else { *first };
+            format!(
+                "also needed by {} {} → {}",
+                operations.get(other).and_then(|op| op.label.clone()).unwrap_or_else(|| format!("#{}", other)),
+                overlap_start.format("%b %-d %H:%M"),
+                overlap_end.format("%H:%M"),
+            )
+        },
+        ToolConflict::Borrowed { .. } => "borrowed, not back yet".to_string(),
+    }
+}
 
 pub trait Query {
     fn query(&mut self, window_table: &mut WindowTable, id: Option<i32>) -> WindowTable;
@@ -7,32 +43,36 @@ impl Query for TableData {
     fn query(&mut self, window_table: &mut WindowTable, id: Option<i32>) -> WindowTable {
         match window_table {
             WindowTable::PreOperativeDefault(_) => {
-                let operations = self.operation.read().unwrap();
-                let patients = self.patient.read().unwrap();
-                let rooms = self.room.read().unwrap();
-                let operation_tools = self.operation_tool.read().unwrap();
+                let integrity = self.integrity();
+                let conflicts = self.conflicts();
+                let operations = self.operation.read();
+                let patients = self.patient.read();
+                let rooms = self.room.read();
+                let operation_tools = self.operation_tool.read();
+                let tools = self.tool.read();
+                let operation_staff = self.operation_staff.read();
+                let staff = self.staff.read();
+                let label_of = |id: i32| operations.get(id).and_then(|op| op.label.clone()).unwrap_or_else(|| format!("#{}", id));
             
                 let list: Vec<PreOperativeDefault> = operations.iter().map(|op| {
                     let op_id = op.id;
                     let op_label = op.label.clone().unwrap_or_else(|| "N/A".to_string());
-                    let op_status = op.status.clone().unwrap_or_else(|| OperationStatus::Discharge);
+                    let op_status = op.status.clone().unwrap_or(OperationStatus::Discharge);
             
-                    let patient_full_name = patients.iter()
-                        .find(|p| op.patient_id.map(|id| id == p.id.unwrap()).unwrap_or(false))
-                        .map(|p| format!("{} {}", p.first_name.clone().unwrap_or_else(|| "N/A".to_string()), p.last_name.clone().unwrap_or_else(|| "N/A".to_string()))) // CONCAT operation
-                        .unwrap_or_else(|| "N/A".to_string()); 
+                    let patient_full_name = op.patient_id.and_then(|id| patients.get(id))
+                        .map(|p| p.full_name()) // CONCAT operation
+                        .unwrap_or_else(|| integrity.missing_label(TableTarget::Operation, op_id, "patient_id"));
             
-                    let room_name = rooms.iter()
-                        .find(|r| op.room_id.map(|id| id == r.id.unwrap()).unwrap_or(false))
+                    let room_name = op.room_id.and_then(|id| rooms.get(id))
                         .map(|r| r.name.clone().unwrap_or_else(|| "N/A".to_string()))
-                        .unwrap_or_else(|| "N/A".to_string()); 
+                        .unwrap_or_else(|| integrity.missing_label(TableTarget::Operation, op_id, "room_id"));
             
                     let total_tools = operation_tools.iter()
-                        .filter(|ot| op_id.map(|id| id == ot.operation_id.unwrap()).unwrap_or(false))
+                        .filter(|ot| op_id.is_some() && ot.operation_id == op_id)
                         .count() as i64;
             
                     let on_site_tools = operation_tools.iter()
-                        .filter(|ot| op_id.map(|id| id == ot.operation_id.unwrap() && match ot.on_site { Some(1) => true, _ => false }).unwrap_or(false))
+                        .filter(|ot| op_id.is_some() && ot.operation_id == op_id && ot.on_site == Some(1))
                         .count() as i64;
             
                     let on_site_ratio = if total_tools > 0 {
@@ -42,10 +82,43 @@ impl Query for TableData {
                     };
             
                     let on_site_percentage = on_site_ratio * 100.0;
-            
-                    let bruh = PreOperativeDefault {
+
+                    let room_conflicts: Vec<String> = conflicts.room_conflicts_of(op_id).into_iter().map(label_of).collect();
+                    let mut missing = Vec::new();
+                    if total_tools > on_site_tools {
+                        missing.push(format!("{} of {} tools not on site", total_tools - on_site_tools, total_tools));
+                    }
+                    let statuses: Vec<Option<EquipmentStatus>> = operation_tools.iter()
+                        .filter(|ot| op_id.is_some() && ot.operation_id == op_id)
+                        .map(|ot| ot.tool_id.and_then(|id| tools.get(id)).and_then(|tool| tool.status.clone()))
+                        .collect();
+                    let for_inspection = statuses.iter().filter(|status| **status == Some(EquipmentStatus::ForInspection)).count();
+                    if for_inspection > 0 {
+                        missing.push(format!("{} tools for inspection", for_inspection));
+                    }
+                    let not_ready = statuses.iter().filter(|status| !matches!(status, Some(EquipmentStatus::Ready) | Some(EquipmentStatus::ForInspection))).count();
+                    if not_ready > 0 {
+                        missing.push(format!("{} tools not ready", not_ready));
+                    }
+                    let roles: Vec<StaffRole> = operation_staff.iter()
+                        .filter(|op_staff| op_id.is_some() && op_staff.operation_id == op_id)
+                        .filter_map(|op_staff| op_staff.staff_id.and_then(|id| staff.get(id)).and_then(|s| s.role.clone()))
+                        .collect();
+                    for role in REQUIRED_ROLES.iter().filter(|role| !roles.contains(role)) {
+                        missing.push(format!("no {:?} assigned", role));
+                    }
+                    if !room_conflicts.is_empty() {
+                        missing.push(format!("room double-booked with {}", room_conflicts.join(", ")));
+                    }
+                    if op.patient_id.and_then(|id| patients.get(id)).is_none() {
+                        missing.push("no patient assigned".to_string());
+                    }
+
+                    PreOperativeDefault {
                         op_id,
                         op_label,
+                        patient_id: op.patient_id,
+                        room_id: op.room_id,
                         patient_full_name,
                         op_status,
                         room_name,
@@ -53,11 +126,13 @@ impl Query for TableData {
                         on_site_tools,
                         on_site_ratio,
                         on_site_percentage,
-                        start_time: op.start_time.clone().unwrap_or_else(|| "N/A".to_string()), 
-                        end_time: op.end_time.clone().unwrap_or_else(|| "N/A".to_string()),   
-                    };
-                    bruh
-                }).collect::<Vec<crate::query_return::PreOperativeDefault>>();
+                        start_time: op.start_time,
+                        end_time: op.end_time,
+                        room_conflicts,
+                        staff_conflicts: staff_conflict_labels(&conflicts, op_id, &staff, &operations),
+                        readiness: Readiness { missing },
+                    }
+                }).collect::<Vec<PreOperativeDefault>>();
 
                 println!("list {:?}", list);
 
@@ -66,28 +141,34 @@ impl Query for TableData {
             },
             WindowTable::PreOperativeToolReady(_) => {
                 if let Some(operation_id) = id {
-                    let operation_tools = self.operation_tool.read().unwrap();
-                    let operations = self.operation.read().unwrap();
-                    let tools = self.tool.read().unwrap();
-                    let equipment = self.equipment.read().unwrap();
+                    let integrity = self.integrity();
+                    let conflicts = self.conflicts();
+                    let operation_tools = self.operation_tool.read();
+                    let operations = self.operation.read();
+                    let tools = self.tool.read();
+                    let equipment = self.equipment.read();
 
                     let mut list: Vec<PreOperativeToolReady> = operation_tools.iter()
-                    .filter(|op_tool| {
-                        operations.iter().any(|op| op.id.unwrap_or_else(|| 0) == op_tool.operation_id.unwrap_or_else(|| -1) && op.id.unwrap_or_else(|| 0) == operation_id)
-                    })
+                    .filter(|op_tool| op_tool.operation_id == Some(operation_id) && operations.get(operation_id).is_some())
                     .map(|op_tool| {
-                        let tool = tools.iter().find(|t| t.id == op_tool.tool_id);
-                        let equipment_item = tool.and_then(|t| equipment.iter().find(|e| e.id == t.info_id));
-                        let tool_name = equipment_item.map_or(
-                            "Unknown Tool".to_string(), 
-                            |e| e.name.clone().unwrap_or_else(|| "N/A".to_string())
-                        );
-                        let tool_status = tool.map_or(EquipmentStatus::ForInspection, |t| t.status.clone().unwrap_or_else(|| EquipmentStatus::ForInspection));
+                        let tool = op_tool.tool_id.and_then(|id| tools.get(id));
+                        let equipment_item = tool.and_then(|t| t.info_id).and_then(|id| equipment.get(id));
+                        let tool_name = match (tool, equipment_item) {
+                            (_, Some(e)) => e.name.clone().unwrap_or_else(|| "N/A".to_string()),
+                            (Some(t), None) => integrity.missing_label(TableTarget::Tool, t.id, "info_id"),
+                            (None, None) => integrity.missing_label(TableTarget::OperationTool, op_tool.id, "tool_id"),
+                        };
+                        let tool_status = tool.map_or(EquipmentStatus::ForInspection, |t| t.status.clone().unwrap_or(EquipmentStatus::ForInspection));
                         
                         PreOperativeToolReady {
+                            op_tool_id: op_tool.id,
+                            tool_id: op_tool.tool_id,
                             equipment_name: tool_name,
-                            on_site: op_tool.on_site.map_or(false, |value| value == 1), // Assuming `on_site` is an Option<bool>
+                            on_site: op_tool.on_site == Some(1), // Assuming `on_site` is an Option<bool>
                             tool_status,
+                            conflicts: conflicts.tool_conflicts_of(Some(operation_id), op_tool.tool_id).into_iter()
+                                .map(|conflict| tool_conflict_label(conflict, operation_id, &operations))
+                                .collect(),
                         }
                     })
                     .collect();
@@ -100,6 +181,511 @@ impl Query for TableData {
                     window_table.to_owned()
                 }
             }
+            WindowTable::InProgressDefault(_) => {
+                let integrity = self.integrity();
+                let conflicts = self.conflicts();
+                let operations = self.operation.read();
+                let patients = self.patient.read();
+                let rooms = self.room.read();
+                let operation_staff = self.operation_staff.read();
+                let staff = self.staff.read();
+
+                let mut list: Vec<InProgressDefault> = operations.iter()
+                .filter(|op| op.status == Some(OperationStatus::InProgress))
+                .map(|op| {
+                    let op_id = op.id;
+                    let op_label = op.label.clone().unwrap_or_else(|| "N/A".to_string());
+
+                    let patient_full_name = op.patient_id.and_then(|id| patients.get(id))
+                        .map(|p| p.full_name())
+                        .unwrap_or_else(|| integrity.missing_label(TableTarget::Operation, op_id, "patient_id"));
+
+                    let room_name = op.room_id.and_then(|id| rooms.get(id))
+                        .map(|r| r.name.clone().unwrap_or_else(|| "N/A".to_string()))
+                        .unwrap_or_else(|| integrity.missing_label(TableTarget::Operation, op_id, "room_id"));
+
+                    let names: Vec<String> = operation_staff.iter()
+                        .filter(|op_staff| op_id.is_some() && op_staff.operation_id == op_id)
+                        .map(|op_staff| op_staff.staff_id.and_then(|id| staff.get(id))
+                            .map(|s| s.full_name())
+                            .unwrap_or_else(|| integrity.missing_label(TableTarget::OperationStaff, op_staff.id, "staff_id")))
+                        .collect();
+
+                    InProgressDefault {
+                        op_id,
+                        op_label,
+                        patient_full_name,
+                        room_name,
+                        staff_count: names.len() as i64,
+                        staff_names: names.join(", "),
+                        staff_conflicts: staff_conflict_labels(&conflicts, op_id, &staff, &operations),
+                        start_time: op.start_time,
+                        end_time: op.end_time,
+                    }
+                })
+                .collect();
+                list.sort_by_key(|op| op.start_time);
+                *window_table = WindowTable::InProgressDefault(Some(list));
+                window_table.to_owned()
+            },
+            WindowTable::PostOperativeDefault(_) => {
+                let integrity = self.integrity();
+                let operations = self.operation.read();
+                let patients = self.patient.read();
+                let rooms = self.room.read();
+
+                let mut list: Vec<PostOperativeDefault> = operations.iter()
+                .filter(|op| op.status == Some(OperationStatus::PostOperative))
+                .map(|op| PostOperativeDefault {
+                    op_id: op.id,
+                    op_label: op.label.clone().unwrap_or_else(|| "N/A".to_string()),
+                    patient_full_name: op.patient_id.and_then(|id| patients.get(id))
+                        .map(|p| p.full_name())
+                        .unwrap_or_else(|| integrity.missing_label(TableTarget::Operation, op.id, "patient_id")),
+                    room_name: op.room_id.and_then(|id| rooms.get(id))
+                        .map(|r| r.name.clone().unwrap_or_else(|| "N/A".to_string()))
+                        .unwrap_or_else(|| integrity.missing_label(TableTarget::Operation, op.id, "room_id")),
+                    start_time: op.start_time,
+                    end_time: op.end_time,
+                })
+                .collect();
+                list.sort_by_key(|op| op.end_time);
+                *window_table = WindowTable::PostOperativeDefault(Some(list));
+                window_table.to_owned()
+            },
+            WindowTable::RecoveryDefault(_) => {
+                let integrity = self.integrity();
+                let operations = self.operation.read();
+                let patients = self.patient.read();
+                let rooms = self.room.read();
+                let ward_rooms = self.patient_ward_room.read();
+                let ward_assistants = self.patient_ward_assistant.read();
+                let staff = self.staff.read();
+
+                let mut list: Vec<RecoveryDefault> = operations.iter()
+                .filter(|op| op.status == Some(OperationStatus::Recovery))
+                .map(|op| {
+                    let patient_full_name = op.patient_id.and_then(|id| patients.get(id))
+                        .map(|p| p.full_name())
+                        .unwrap_or_else(|| integrity.missing_label(TableTarget::Operation, op.id, "patient_id"));
+
+                    let ward_room = current_ward_room(&ward_rooms, op.patient_id);
+                    let ward_room_name = match ward_room {
+                        Some(ward_room) => ward_room.room_id.and_then(|id| rooms.get(id))
+                            .map(|r| r.name.clone().unwrap_or_else(|| "N/A".to_string()))
+                            .unwrap_or_else(|| integrity.missing_label(TableTarget::PatientWardRoom, ward_room.id, "room_id")),
+                        None => "unassigned".to_string(),
+                    };
+
+                    let assistant_names: Vec<String> = ward_assistants.iter()
+                        .filter(|assistant| ward_room.is_some_and(|ward_room| ward_room.id.is_some() && assistant.patient_ward_room_id == ward_room.id))
+                        .map(|assistant| assistant.staff_id.and_then(|id| staff.get(id))
+                            .map(|s| s.full_name())
+                            .unwrap_or_else(|| integrity.missing_label(TableTarget::PatientWardAssistant, assistant.id, "staff_id")))
+                        .collect();
+
+                    RecoveryDefault {
+                        op_id: op.id,
+                        op_label: op.label.clone().unwrap_or_else(|| "N/A".to_string()),
+                        patient_full_name,
+                        ward_room_name,
+                        assistant_names: if assistant_names.is_empty() { "none".to_string() } else { assistant_names.join(", ") },
+                        end_time: op.end_time,
+                    }
+                })
+                .collect();
+                list.sort_by_key(|op| op.end_time);
+                *window_table = WindowTable::RecoveryDefault(Some(list));
+                window_table.to_owned()
+            },
+            WindowTable::DischargeDefault(_) => {
+                let integrity = self.integrity();
+                let operations = self.operation.read();
+                let patients = self.patient.read();
+                let rooms = self.room.read();
+                let today = local_now().date();
+
+                // there is no discharge timestamp, so the end of the operation stands in for it
+                let mut list: Vec<DischargeDefault> = operations.iter()
+                .filter(|op| op.status == Some(OperationStatus::Discharge) && op.end_time.is_some_and(|end_time| end_time.date() == today))
+                .map(|op| {
+                    let patient = op.patient_id.and_then(|id| patients.get(id));
+                    DischargeDefault {
+                        op_id: op.id,
+                        op_label: op.label.clone().unwrap_or_else(|| "N/A".to_string()),
+                        patient_full_name: patient
+                            .map(|p| p.full_name())
+                            .unwrap_or_else(|| integrity.missing_label(TableTarget::Operation, op.id, "patient_id")),
+                        patient_phone: patient.and_then(|p| p.phone.clone()).unwrap_or_else(|| "N/A".to_string()),
+                        room_name: op.room_id.and_then(|id| rooms.get(id))
+                            .map(|r| r.name.clone().unwrap_or_else(|| "N/A".to_string()))
+                            .unwrap_or_else(|| integrity.missing_label(TableTarget::Operation, op.id, "room_id")),
+                        end_time: op.end_time,
+                    }
+                })
+                .collect();
+                list.sort_by_key(|op| op.end_time);
+                *window_table = WindowTable::DischargeDefault(Some(list));
+                window_table.to_owned()
+            },
+            WindowTable::PatientData(_) => {
+                let Some(patient_id) = id else {
+                    return window_table.to_owned();
+                };
+                let integrity = self.integrity();
+                let patients = self.patient.read();
+                let operations = self.operation.read();
+                let rooms = self.room.read();
+                let ward_rooms = self.patient_ward_room.read();
+                let ward_assistants = self.patient_ward_assistant.read();
+                let staff = self.staff.read();
+
+                let patient = patients.get(patient_id);
+                let ward_room = current_ward_room(&ward_rooms, Some(patient_id));
+                let ward_room_name = match ward_room {
+                    Some(ward_room) => ward_room.room_id.and_then(|id| rooms.get(id))
+                        .map(|r| r.name.clone().unwrap_or_else(|| "N/A".to_string()))
+                        .unwrap_or_else(|| integrity.missing_label(TableTarget::PatientWardRoom, ward_room.id, "room_id")),
+                    None => "unassigned".to_string(),
+                };
+
+                let assistants: Vec<WardAssistant> = ward_assistants.iter()
+                    .filter(|assistant| ward_room.is_some_and(|ward_room| ward_room.id.is_some() && assistant.patient_ward_room_id == ward_room.id))
+                    .map(|assistant| {
+                        let staff_member = assistant.staff_id.and_then(|id| staff.get(id));
+                        WardAssistant {
+                            full_name: staff_member
+                                .map(|s| s.full_name())
+                                .unwrap_or_else(|| integrity.missing_label(TableTarget::PatientWardAssistant, assistant.id, "staff_id")),
+                            phone: staff_member.and_then(|s| s.phone.clone()).unwrap_or_else(|| "N/A".to_string()),
+                        }
+                    })
+                    .collect();
+
+                let mut history: Vec<PatientOperation> = operations.iter()
+                    .filter(|op| op.patient_id == Some(patient_id))
+                    .map(|op| PatientOperation {
+                        op_id: op.id,
+                        op_label: op.label.clone().unwrap_or_else(|| "N/A".to_string()),
+                        op_status: op.status.clone(),
+                        room_name: op.room_id.and_then(|id| rooms.get(id))
+                            .map(|r| r.name.clone().unwrap_or_else(|| "N/A".to_string()))
+                            .unwrap_or_else(|| integrity.missing_label(TableTarget::Operation, op.id, "room_id")),
+                        start_time: op.start_time,
+                        end_time: op.end_time,
+                    })
+                    .collect();
+                // newest first
+                history.sort_by_key(|op| std::cmp::Reverse(op.start_time));
+
+                *window_table = WindowTable::PatientData(Some(PatientData {
+                    patient_id: Some(patient_id),
+                    full_name: patient
+                        .map(|p| p.full_name())
+                        .unwrap_or_else(|| format!("⚠ missing Patient #{}", patient_id)),
+                    email: patient.and_then(|p| p.email.clone()).unwrap_or_else(|| "N/A".to_string()),
+                    phone: patient.and_then(|p| p.phone.clone()).unwrap_or_else(|| "N/A".to_string()),
+                    ward_room_name,
+                    assistants,
+                    history,
+                }));
+                window_table.to_owned()
+            },
+            WindowTable::RoomProperty(_) => {
+                let Some(room_id) = id else {
+                    return window_table.to_owned();
+                };
+                let integrity = self.integrity();
+                let rooms = self.room.read();
+                let patients = self.patient.read();
+                let staff = self.staff.read();
+                let ward_rooms = self.patient_ward_room.read();
+                let ward_assistants = self.patient_ward_assistant.read();
+                let operations = self.operation.read();
+                let operation_staff = self.operation_staff.read();
+                let designated_rooms = self.tool_designated_room.read();
+                let tools = self.tool.read();
+                let equipment = self.equipment.read();
+                let today = local_now().date();
+
+                let room = rooms.get(room_id);
+                let patient_name = |id: Option<i32>| id.and_then(|id| patients.get(id))
+                    .map(|p| p.full_name());
+                let staff_name = |id: Option<i32>| id.and_then(|id| staff.get(id))
+                    .map(|s| s.full_name());
+
+                // patients whose current ward room is this one
+                let warded: Vec<&PatientWardRoom> = ward_rooms.iter()
+                    .filter(|ward_room| ward_room.room_id == Some(room_id))
+                    .filter(|ward_room| current_ward_room(&ward_rooms, ward_room.patient_id).is_some_and(|current| current.id == ward_room.id))
+                    .collect();
+                let running: Vec<_> = operations.iter()
+                    .filter(|op| op.room_id == Some(room_id) && op.status == Some(OperationStatus::InProgress))
+                    .collect();
+
+                let mut patient_ids: Vec<Option<i32>> = warded.iter().map(|ward_room| ward_room.patient_id)
+                    .chain(running.iter().map(|op| op.patient_id))
+                    .collect();
+                patient_ids.sort();
+                patient_ids.dedup();
+                let mut staff_ids: Vec<Option<i32>> = ward_assistants.iter()
+                    .filter(|assistant| warded.iter().any(|ward_room| ward_room.id.is_some() && assistant.patient_ward_room_id == ward_room.id))
+                    .map(|assistant| assistant.staff_id)
+                    .chain(operation_staff.iter()
+                        .filter(|op_staff| running.iter().any(|op| op.id.is_some() && op_staff.operation_id == op.id))
+                        .map(|op_staff| op_staff.staff_id))
+                    .collect();
+                staff_ids.sort();
+                staff_ids.dedup();
+
+                let room_tools: Vec<RoomTool> = designated_rooms.iter()
+                    .filter(|designated| designated.room_id == Some(room_id))
+                    .map(|designated| {
+                        let tool = designated.tool_id.and_then(|id| tools.get(id));
+                        let equipment_name = match (tool, tool.and_then(|t| t.info_id).and_then(|id| equipment.get(id))) {
+                            (_, Some(e)) => e.name.clone().unwrap_or_else(|| "N/A".to_string()),
+                            (Some(t), None) => integrity.missing_label(TableTarget::Tool, t.id, "info_id"),
+                            (None, None) => integrity.missing_label(TableTarget::ToolDesignatedRoom, designated.id, "tool_id"),
+                        };
+                        RoomTool {
+                            tool_id: designated.tool_id,
+                            equipment_name,
+                            tool_status: tool.and_then(|t| t.status.clone()),
+                        }
+                    })
+                    .collect();
+
+                let mut room_operations: Vec<RoomOperation> = operations.iter()
+                    .filter(|op| op.room_id == Some(room_id) && op.start_time.is_some_and(|start_time| start_time.date() == today))
+                    .map(|op| RoomOperation {
+                        op_id: op.id,
+                        op_label: op.label.clone().unwrap_or_else(|| "N/A".to_string()),
+                        op_status: op.status.clone(),
+                        patient_full_name: patient_name(op.patient_id)
+                            .unwrap_or_else(|| integrity.missing_label(TableTarget::Operation, op.id, "patient_id")),
+                        start_time: op.start_time,
+                        end_time: op.end_time,
+                    })
+                    .collect();
+                room_operations.sort_by_key(|op| op.start_time);
+
+                *window_table = WindowTable::RoomProperty(Some(RoomProperty {
+                    room_id: Some(room_id),
+                    name: room.and_then(|r| r.name.clone())
+                        .unwrap_or_else(|| if room.is_some() { "N/A".to_string() } else { format!("⚠ missing Room #{}", room_id) }),
+                    alias_code: room.and_then(|r| r.alias_code.clone()).unwrap_or_else(|| "N/A".to_string()),
+                    purpose: room.and_then(|r| r.purpose.clone()),
+                    patient_maximum_occupancy: room.and_then(|r| r.patient_maximum_occupancy),
+                    staff_maximum_occupancy: room.and_then(|r| r.staff_maximum_occupancy),
+                    patients: patient_ids.into_iter().map(|id| patient_name(id).unwrap_or_else(|| "N/A".to_string())).collect(),
+                    staff: staff_ids.into_iter().map(|id| staff_name(id).unwrap_or_else(|| "N/A".to_string())).collect(),
+                    tools: room_tools,
+                    operations: room_operations,
+                }));
+                window_table.to_owned()
+            },
+            WindowTable::CalendarVisualization(_) => {
+                let conflicts = self.conflicts();
+                let operations = self.operation.read();
+                let rooms = self.room.read();
+                let patients = self.patient.read();
+                let operation_tools = self.operation_tool.read();
+                let staff = self.staff.read();
+
+                let mut lanes: Vec<CalendarLane> = rooms.iter()
+                    .map(|room| CalendarLane {
+                        room_id: room.id,
+                        name: room.name.clone().unwrap_or_else(|| "N/A".to_string()),
+                        blocks: Vec::new(),
+                    })
+                    .collect();
+                lanes.sort_by(|a, b| a.name.cmp(&b.name));
+                let mut unassigned = CalendarLane { room_id: None, name: "unassigned".to_string(), blocks: Vec::new() };
+
+                for op in operations.iter().filter(|op| op.start_time.is_some() && op.end_time.is_some()) {
+                    let block = calendar_block(op, &conflicts, &operations, &rooms, &patients, &operation_tools, &staff);
+                    match lanes.iter_mut().find(|lane| op.room_id.is_some() && lane.room_id == op.room_id) {
+                        Some(lane) => lane.blocks.push(block),
+                        None => unassigned.blocks.push(block),
+                    }
+                }
+                if !unassigned.blocks.is_empty() {
+                    lanes.push(unassigned);
+                }
+                *window_table = WindowTable::CalendarVisualization(Some(lanes));
+                window_table.to_owned()
+            },
+            WindowTable::OperationTeam(_) => {
+                let Some(operation_id) = id else {
+                    return window_table.to_owned();
+                };
+                let integrity = self.integrity();
+                let operations = self.operation.read();
+                let operation_staff = self.operation_staff.read();
+                let staff = self.staff.read();
+
+                let mut members: Vec<TeamMember> = operation_staff.iter()
+                    .filter(|op_staff| op_staff.operation_id == Some(operation_id))
+                    .map(|op_staff| match op_staff.staff_id.and_then(|id| staff.get(id)) {
+                        Some(s) => TeamMember {
+                            staff_id: s.id,
+                            full_name: s.full_name(),
+                            role: s.role.clone(),
+                            phone: s.phone.clone().unwrap_or_else(|| "N/A".to_string()),
+                            email: s.email.clone().unwrap_or_else(|| "N/A".to_string()),
+                        },
+                        None => TeamMember {
+                            staff_id: op_staff.staff_id,
+                            full_name: integrity.missing_label(TableTarget::OperationStaff, op_staff.id, "staff_id"),
+                            role: None,
+                            phone: "N/A".to_string(),
+                            email: "N/A".to_string(),
+                        },
+                    })
+                    .collect();
+                members.sort_by(|a, b| a.role.partial_cmp(&b.role).unwrap_or(std::cmp::Ordering::Equal).then_with(|| a.full_name.cmp(&b.full_name)));
+                let missing_roles = REQUIRED_ROLES.iter()
+                    .filter(|role| !members.iter().any(|member| member.role.as_ref() == Some(*role)))
+                    .cloned()
+                    .collect();
+                *window_table = WindowTable::OperationTeam(Some(OperationTeam {
+                    op_id: Some(operation_id),
+                    op_label: operations.get(operation_id).and_then(|op| op.label.clone()).unwrap_or_else(|| "N/A".to_string()),
+                    members,
+                    missing_roles,
+                }));
+                window_table.to_owned()
+            },
+            WindowTable::ToolDetail(_) => {
+                let Some(tool_id) = id else {
+                    return window_table.to_owned();
+                };
+                let integrity = self.integrity();
+                let tools = self.tool.read();
+                let equipment = self.equipment.read();
+                let designated_rooms = self.tool_designated_room.read();
+                let rooms = self.room.read();
+                let inspectors = self.tool_inspector.read();
+                let staff = self.staff.read();
+                let operation_tools = self.operation_tool.read();
+                let operations = self.operation.read();
+                let Some(tool) = tools.get(tool_id) else {
+                    return window_table.to_owned();
+                };
+                let staff_name = |staff_id: Option<i32>| staff_id.and_then(|id| staff.get(id))
+                    .map(|s| s.full_name())
+                    .unwrap_or_else(|| "N/A".to_string());
+
+                let equipment_item = tool.info_id.and_then(|id| equipment.get(id));
+                let text = |value: Option<&Option<String>>| value.and_then(|value| value.clone()).unwrap_or_else(|| "N/A".to_string());
+                let mut tool_operations: Vec<ToolOperation> = operation_tools.iter()
+                    .filter(|op_tool| op_tool.tool_id == Some(tool_id))
+                    .filter_map(|op_tool| op_tool.operation_id.and_then(|id| operations.get(id)).map(|op| (op_tool, op)))
+                    .map(|(op_tool, op)| ToolOperation {
+                        op_id: op.id,
+                        op_label: op.label.clone().unwrap_or_else(|| "N/A".to_string()),
+                        op_status: op.status.clone(),
+                        room_name: op.room_id.and_then(|id| rooms.get(id))
+                            .map(|r| r.name.clone().unwrap_or_else(|| "N/A".to_string()))
+                            .unwrap_or_else(|| integrity.missing_label(TableTarget::Operation, op.id, "room_id")),
+                        on_site: op_tool.on_site == Some(1),
+                        start_time: op.start_time,
+                        end_time: op.end_time,
+                    })
+                    .collect();
+                tool_operations.sort_by_key(|op| op.start_time);
+
+                *window_table = WindowTable::ToolDetail(Some(ToolDetail {
+                    tool_id: Some(tool_id),
+                    equipment_name: match equipment_item {
+                        Some(e) => e.name.clone().unwrap_or_else(|| "N/A".to_string()),
+                        None => integrity.missing_label(TableTarget::Tool, tool.id, "info_id"),
+                    },
+                    serial_number: text(equipment_item.map(|e| &e.serial_number)),
+                    manufacturer: text(equipment_item.map(|e| &e.manufacturer)),
+                    brand: text(equipment_item.map(|e| &e.brand)),
+                    tool_status: tool.status.clone(),
+                    designated_rooms: designated_rooms.iter()
+                        .filter(|designated| designated.tool_id == Some(tool_id))
+                        .map(|designated| (
+                            designated.room_id,
+                            designated.room_id.and_then(|id| rooms.get(id))
+                                .map(|r| r.name.clone().unwrap_or_else(|| "N/A".to_string()))
+                                .unwrap_or_else(|| integrity.missing_label(TableTarget::ToolDesignatedRoom, designated.id, "room_id")),
+                        ))
+                        .collect(),
+                    inspectors: inspectors.iter()
+                        .filter(|inspector| inspector.tool_id == Some(tool_id))
+                        .map(|inspector| staff_name(inspector.staff_id))
+                        .collect(),
+                    operations: tool_operations,
+                }));
+                window_table.to_owned()
+            },
+            WindowTable::StaffTimeline(_) => {
+                if let Some(operation_id) = id {
+                    let conflicts = self.conflicts();
+                    let operations = self.operation.read();
+                    let rooms = self.room.read();
+                    let patients = self.patient.read();
+                    let operation_tools = self.operation_tool.read();
+                    let operation_staff = self.operation_staff.read();
+                    let staff = self.staff.read();
+
+                    let mut staff_ids: Vec<i32> = operation_staff.iter()
+                        .filter(|op_staff| op_staff.operation_id == Some(operation_id))
+                        .filter_map(|op_staff| op_staff.staff_id)
+                        .collect();
+                    staff_ids.sort();
+                    staff_ids.dedup();
+                    let lanes: Vec<CalendarLane> = staff_ids.into_iter()
+                        .map(|staff_id| CalendarLane {
+                            // staff lanes are not rooms, so dropping a block on one only moves it in time
+                            room_id: None,
+                            name: staff.get(staff_id)
+                                .map(|s| s.full_name())
+                                .unwrap_or_else(|| format!("staff #{}", staff_id)),
+                            blocks: operation_staff.iter()
+                                .filter(|op_staff| op_staff.staff_id == Some(staff_id))
+                                .filter_map(|op_staff| op_staff.operation_id.and_then(|id| operations.get(id)))
+                                .filter(|op| op.start_time.is_some() && op.end_time.is_some())
+                                .map(|op| calendar_block(op, &conflicts, &operations, &rooms, &patients, &operation_tools, &staff))
+                                .collect(),
+                        })
+                        .collect();
+                    *window_table = WindowTable::StaffTimeline(Some(lanes));
+                    window_table.to_owned()
+                } else {
+                    window_table.to_owned()
+                }
+            },
         }
     }
-}
\ No newline at end of file
+}
+
+/// One operation as drawn on a calendar lane.
+fn calendar_block(op: &Operation, conflicts: &ConflictReport, operations: &Rows<Operation>, rooms: &Rows<Room>, patients: &Rows<Patient>, operation_tools: &Rows<OperationTool>, staff: &Rows<Staff>) -> CalendarBlock {
+    let total_tools = operation_tools.iter()
+        .filter(|ot| op.id.is_some() && ot.operation_id == op.id)
+        .count();
+    let on_site_tools = operation_tools.iter()
+        .filter(|ot| op.id.is_some() && ot.operation_id == op.id && ot.on_site == Some(1))
+        .count();
+    CalendarBlock {
+        op_id: op.id,
+        op_label: op.label.clone().unwrap_or_else(|| "N/A".to_string()),
+        patient_full_name: op.patient_id.and_then(|id| patients.get(id))
+            .map(|p| p.full_name())
+            .unwrap_or_else(|| "N/A".to_string()),
+        room_name: op.room_id.and_then(|id| rooms.get(id))
+            .map_or_else(|| "unassigned".to_string(), |room| room.name.clone().unwrap_or_else(|| "N/A".to_string())),
+        op_status: op.status.clone(),
+        on_site_percentage: if total_tools > 0 { on_site_tools as f64 / total_tools as f64 * 100.0 } else { 0.0 },
+        start_time: op.start_time,
+        end_time: op.end_time,
+        room_conflicts: conflicts.room_conflicts_of(op.id).into_iter()
+            .map(|id| operations.get(id).and_then(|other| other.label.clone()).unwrap_or_else(|| format!("#{}", id)))
+            .collect(),
+        staff_conflicts: staff_conflict_labels(conflicts, op.id, staff, operations),
+    }
+}
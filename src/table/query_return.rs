@@ -1,40 +1,445 @@
-
-use serde::{Deserialize, Serialize};
-
-use crate::database;
-
-use super::EquipmentStatus;
-
-#[derive(Debug, Clone)]
-pub struct TableTree {
-    pub data: WindowTable,
-    pub child: Option<Box<TableTree>>
-}
-
-#[derive(Debug, Clone)]
-pub enum WindowTable {
-    PreOperativeDefault(Option<Vec<PreOperativeDefault>>),
-    PreOperativeToolReady(Option<Vec<PreOperativeToolReady>>)
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PreOperativeDefault {
-    pub op_id: Option<i32>,
-    pub op_label: String,
-    pub patient_full_name: String,
-    pub op_status: database::table::OperationStatus,
-    pub room_name: String,
-    pub total_tools: i64,
-    pub on_site_tools: i64,
-    pub on_site_ratio: f64,
-    pub on_site_percentage: f64,
-    pub start_time: String,
-    pub end_time: String
-}
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct PreOperativeToolReady {
-    pub equipment_name: String,
-    pub tool_status: crate::database::table::EquipmentStatus,
-    pub on_site: bool,
-}
-
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::{database::{self, table::StaffRole}, TableTarget};
+
+use super::{ConflictsKey, Query, TableData};
+
+#[derive(Debug, Clone)]
+pub struct TableTree {
+    pub data: WindowTable,
+    pub child: Option<Box<TableTree>>,
+    /// Id the query was run with, kept so the node can re-run it.
+    pub id: Option<i32>,
+    /// Versions of `data.dependencies()` when the query last ran.
+    pub versions: Vec<u64>,
+    /// Key of the conflict check the query last saw, for views that show conflicts.
+    pub conflicts_key: Option<ConflictsKey>,
+}
+impl TableTree {
+    pub fn new(table_data: &mut TableData, mut window_table: WindowTable, id: Option<i32>) -> Self {
+        let versions = table_data.versions(window_table.dependencies());
+        let conflicts_key = window_table.shows_conflicts().then(|| table_data.conflicts_key());
+        let data = table_data.query(&mut window_table, id);
+        TableTree {
+            data,
+            child: None,
+            id,
+            versions,
+            conflicts_key,
+        }
+    }
+    /// The innermost node, which is the one on screen.
+    pub fn last(&self) -> &TableTree {
+        match &self.child {
+            Some(child) => child.last(),
+            None => self,
+        }
+    }
+    pub fn push(&mut self, table_tree: TableTree) {
+        match &mut self.child {
+            Some(child) => child.push(table_tree),
+            None => self.child = Some(Box::new(table_tree)),
+        }
+    }
+    /// The node the innermost one was opened from, if there is one.
+    pub fn parent_of_last(&self) -> Option<&TableTree> {
+        match &self.child {
+            Some(child) if child.child.is_some() => child.parent_of_last(),
+            Some(_) => Some(self),
+            None => None,
+        }
+    }
+    /// Drops the innermost node. The root always stays.
+    pub fn pop(&mut self) {
+        if let Some(child) = &mut self.child {
+            if child.child.is_some() {
+                child.pop();
+            } else {
+                self.child = None;
+            }
+        }
+    }
+    /// Views from the root down to the one on screen.
+    pub fn path(&self) -> Vec<&WindowTable> {
+        let mut path = vec![&self.data];
+        if let Some(child) = &self.child {
+            path.extend(child.path());
+        }
+        path
+    }
+    /// Re-runs the query of this node and its children whose tables, or
+    /// conflicts, changed.
+    pub fn refresh(&mut self, table_data: &mut TableData) {
+        let versions = table_data.versions(self.data.dependencies());
+        let conflicts_key = self.data.shows_conflicts().then(|| table_data.conflicts_key());
+        if versions != self.versions || conflicts_key != self.conflicts_key {
+            self.data = table_data.query(&mut self.data, self.id);
+            self.versions = versions;
+            self.conflicts_key = conflicts_key;
+        }
+        if let Some(child) = &mut self.child {
+            child.refresh(table_data);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum WindowTable {
+    PreOperativeDefault(Option<Vec<PreOperativeDefault>>),
+    PreOperativeToolReady(Option<Vec<PreOperativeToolReady>>),
+    InProgressDefault(Option<Vec<InProgressDefault>>),
+    PostOperativeDefault(Option<Vec<PostOperativeDefault>>),
+    RecoveryDefault(Option<Vec<RecoveryDefault>>),
+    DischargeDefault(Option<Vec<DischargeDefault>>),
+    PatientData(Option<PatientData>),
+    RoomProperty(Option<RoomProperty>),
+    CalendarVisualization(Option<Vec<CalendarLane>>),
+    /// One lane per member of an operation's staff with all their operations.
+    StaffTimeline(Option<Vec<CalendarLane>>),
+    OperationTeam(Option<OperationTeam>),
+    ToolDetail(Option<ToolDetail>),
+}
+impl WindowTable {
+    /// Whether the query for this view reads `TableData::conflicts()`.
+    pub fn shows_conflicts(&self) -> bool {
+        matches!(
+            self,
+            WindowTable::PreOperativeDefault(_)
+                | WindowTable::PreOperativeToolReady(_)
+                | WindowTable::InProgressDefault(_)
+                | WindowTable::CalendarVisualization(_)
+                | WindowTable::StaffTimeline(_)
+        )
+    }
+    /// Tables the query for this view reads from.
+    pub fn dependencies(&self) -> &'static [TableTarget] {
+        match self {
+            WindowTable::PreOperativeDefault(_) => &[
+                TableTarget::Operation,
+                TableTarget::Patient,
+                TableTarget::Room,
+                TableTarget::OperationTool,
+                TableTarget::Tool,
+                TableTarget::OperationStaff,
+                TableTarget::Staff,
+            ],
+            WindowTable::PreOperativeToolReady(_) => &[
+                TableTarget::OperationTool,
+                TableTarget::Operation,
+                TableTarget::Tool,
+                TableTarget::Equipment,
+                TableTarget::Staff,
+            ],
+            WindowTable::InProgressDefault(_) => &[
+                TableTarget::Operation,
+                TableTarget::Patient,
+                TableTarget::Room,
+                TableTarget::OperationStaff,
+                TableTarget::Staff,
+            ],
+            WindowTable::PostOperativeDefault(_) => &[
+                TableTarget::Operation,
+                TableTarget::Patient,
+                TableTarget::Room,
+            ],
+            WindowTable::RecoveryDefault(_) => &[
+                TableTarget::Operation,
+                TableTarget::Patient,
+                TableTarget::Room,
+                TableTarget::PatientWardRoom,
+                TableTarget::PatientWardAssistant,
+                TableTarget::Staff,
+            ],
+            WindowTable::DischargeDefault(_) => &[
+                TableTarget::Operation,
+                TableTarget::Patient,
+                TableTarget::Room,
+            ],
+            WindowTable::PatientData(_) => &[
+                TableTarget::Patient,
+                TableTarget::Operation,
+                TableTarget::Room,
+                TableTarget::PatientWardRoom,
+                TableTarget::PatientWardAssistant,
+                TableTarget::Staff,
+            ],
+            WindowTable::RoomProperty(_) => &[
+                TableTarget::Room,
+                TableTarget::Patient,
+                TableTarget::Staff,
+                TableTarget::PatientWardRoom,
+                TableTarget::PatientWardAssistant,
+                TableTarget::Operation,
+                TableTarget::OperationStaff,
+                TableTarget::ToolDesignatedRoom,
+                TableTarget::Tool,
+                TableTarget::Equipment,
+            ],
+            WindowTable::CalendarVisualization(_) => &[
+                TableTarget::Operation,
+                TableTarget::Room,
+                TableTarget::Patient,
+                TableTarget::OperationTool,
+                TableTarget::OperationStaff,
+                TableTarget::Staff,
+            ],
+            WindowTable::OperationTeam(_) => &[
+                TableTarget::Operation,
+                TableTarget::OperationStaff,
+                TableTarget::Staff,
+            ],
+            WindowTable::ToolDetail(_) => &[
+                TableTarget::Tool,
+                TableTarget::Equipment,
+                TableTarget::ToolDesignatedRoom,
+                TableTarget::Room,
+                TableTarget::ToolInspector,
+                TableTarget::Staff,
+                TableTarget::OperationTool,
+                TableTarget::Operation,
+            ],
+            WindowTable::StaffTimeline(_) => &[
+                TableTarget::Operation,
+                TableTarget::Room,
+                TableTarget::Patient,
+                TableTarget::OperationTool,
+                TableTarget::OperationStaff,
+                TableTarget::Staff,
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreOperativeDefault {
+    pub op_id: Option<i32>,
+    pub op_label: String,
+    pub patient_id: Option<i32>,
+    pub room_id: Option<i32>,
+    pub patient_full_name: String,
+    pub op_status: database::table::OperationStatus,
+    pub room_name: String,
+    pub total_tools: i64,
+    pub on_site_tools: i64,
+    pub on_site_ratio: f64,
+    pub on_site_percentage: f64,
+    #[serde(with = "crate::database::datetime")]
+    pub start_time: Option<NaiveDateTime>,
+    #[serde(with = "crate::database::datetime")]
+    pub end_time: Option<NaiveDateTime>,
+    /// Labels of operations booked in the same room at the same time.
+    pub room_conflicts: Vec<String>,
+    pub staff_conflicts: Vec<String>,
+    pub readiness: Readiness,
+}
+/// Roles every operation needs on its staff before it can start.
+pub const REQUIRED_ROLES: &[StaffRole] = &[StaffRole::Physician, StaffRole::Nurse];
+/// Go/no-go for an operation, with what is still missing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Readiness {
+    pub missing: Vec<String>,
+}
+impl Readiness {
+    pub fn is_go(&self) -> bool {
+        self.missing.is_empty()
+    }
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PreOperativeToolReady {
+    /// The `OperationTool` row, which holds `on_site`.
+    pub op_tool_id: Option<i32>,
+    pub tool_id: Option<i32>,
+    pub equipment_name: String,
+    pub tool_status: crate::database::table::EquipmentStatus,
+    pub on_site: bool,
+    /// Why the tool cannot be in the operating room when needed.
+    pub conflicts: Vec<String>,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InProgressDefault {
+    pub op_id: Option<i32>,
+    pub op_label: String,
+    pub patient_full_name: String,
+    pub room_name: String,
+    /// Names of the staff on the operation, joined with ", ".
+    pub staff_names: String,
+    pub staff_count: i64,
+    pub staff_conflicts: Vec<String>,
+    #[serde(with = "crate::database::datetime")]
+    pub start_time: Option<NaiveDateTime>,
+    #[serde(with = "crate::database::datetime")]
+    pub end_time: Option<NaiveDateTime>,
+}
+impl InProgressDefault {
+    /// Elapsed over planned time at `now`; above 1.0 once the operation overruns.
+    pub fn progress(&self, now: NaiveDateTime) -> Option<f32> {
+        let (start_time, end_time) = (self.start_time?, self.end_time?);
+        let planned = (end_time - start_time).num_seconds();
+        if planned <= 0 {
+            return Some(1.0);
+        }
+        Some((now - start_time).num_seconds() as f32 / planned as f32)
+    }
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PostOperativeDefault {
+    pub op_id: Option<i32>,
+    pub op_label: String,
+    pub patient_full_name: String,
+    pub room_name: String,
+    #[serde(with = "crate::database::datetime")]
+    pub start_time: Option<NaiveDateTime>,
+    #[serde(with = "crate::database::datetime")]
+    pub end_time: Option<NaiveDateTime>,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecoveryDefault {
+    pub op_id: Option<i32>,
+    pub op_label: String,
+    pub patient_full_name: String,
+    /// Ward room the patient is assigned to through `PatientWardRoom`.
+    pub ward_room_name: String,
+    /// Staff assigned to that ward room through `PatientWardAssistant`, joined with ", ".
+    pub assistant_names: String,
+    #[serde(with = "crate::database::datetime")]
+    pub end_time: Option<NaiveDateTime>,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DischargeDefault {
+    pub op_id: Option<i32>,
+    pub op_label: String,
+    pub patient_full_name: String,
+    pub patient_phone: String,
+    pub room_name: String,
+    #[serde(with = "crate::database::datetime")]
+    pub end_time: Option<NaiveDateTime>,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatientData {
+    pub patient_id: Option<i32>,
+    pub full_name: String,
+    pub email: String,
+    pub phone: String,
+    pub ward_room_name: String,
+    pub assistants: Vec<WardAssistant>,
+    /// Every operation of the patient, newest first.
+    pub history: Vec<PatientOperation>,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WardAssistant {
+    pub full_name: String,
+    pub phone: String,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PatientOperation {
+    pub op_id: Option<i32>,
+    pub op_label: String,
+    pub op_status: Option<database::table::OperationStatus>,
+    pub room_name: String,
+    #[serde(with = "crate::database::datetime")]
+    pub start_time: Option<NaiveDateTime>,
+    #[serde(with = "crate::database::datetime")]
+    pub end_time: Option<NaiveDateTime>,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomProperty {
+    pub room_id: Option<i32>,
+    pub name: String,
+    pub alias_code: String,
+    pub purpose: Option<database::table::RoomPurpose>,
+    pub patient_maximum_occupancy: Option<i32>,
+    pub staff_maximum_occupancy: Option<i32>,
+    /// Patients warded here plus those in an operation running here.
+    pub patients: Vec<String>,
+    /// Ward assistants of those patients plus staff of a running operation.
+    pub staff: Vec<String>,
+    pub tools: Vec<RoomTool>,
+    /// Operations scheduled in the room today, by start time.
+    pub operations: Vec<RoomOperation>,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OperationTeam {
+    pub op_id: Option<i32>,
+    pub op_label: String,
+    pub members: Vec<TeamMember>,
+    /// Roles from `REQUIRED_ROLES` nobody on the team has.
+    pub missing_roles: Vec<StaffRole>,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TeamMember {
+    pub staff_id: Option<i32>,
+    pub full_name: String,
+    pub role: Option<StaffRole>,
+    pub phone: String,
+    pub email: String,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolDetail {
+    pub tool_id: Option<i32>,
+    pub equipment_name: String,
+    pub serial_number: String,
+    pub manufacturer: String,
+    pub brand: String,
+    pub tool_status: Option<database::table::EquipmentStatus>,
+    /// `(room_id, name)` of the rooms the tool belongs in.
+    pub designated_rooms: Vec<(Option<i32>, String)>,
+    pub inspectors: Vec<String>,
+    /// Every operation the tool is listed for, by start time.
+    pub operations: Vec<ToolOperation>,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolOperation {
+    pub op_id: Option<i32>,
+    pub op_label: String,
+    pub op_status: Option<database::table::OperationStatus>,
+    pub room_name: String,
+    pub on_site: bool,
+    #[serde(with = "crate::database::datetime")]
+    pub start_time: Option<NaiveDateTime>,
+    #[serde(with = "crate::database::datetime")]
+    pub end_time: Option<NaiveDateTime>,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomTool {
+    pub tool_id: Option<i32>,
+    pub equipment_name: String,
+    pub tool_status: Option<database::table::EquipmentStatus>,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoomOperation {
+    pub op_id: Option<i32>,
+    pub op_label: String,
+    pub op_status: Option<database::table::OperationStatus>,
+    pub patient_full_name: String,
+    #[serde(with = "crate::database::datetime")]
+    pub start_time: Option<NaiveDateTime>,
+    #[serde(with = "crate::database::datetime")]
+    pub end_time: Option<NaiveDateTime>,
+}
+/// One row of a calendar, a room or a member of staff, with every
+/// operation booked on it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarLane {
+    /// The room blocks dropped on this lane move to, `None` for lanes that are not a room.
+    pub room_id: Option<i32>,
+    pub name: String,
+    pub blocks: Vec<CalendarBlock>,
+}
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CalendarBlock {
+    pub op_id: Option<i32>,
+    pub op_label: String,
+    pub patient_full_name: String,
+    pub room_name: String,
+    pub op_status: Option<database::table::OperationStatus>,
+    pub on_site_percentage: f64,
+    #[serde(with = "crate::database::datetime")]
+    pub start_time: Option<NaiveDateTime>,
+    #[serde(with = "crate::database::datetime")]
+    pub end_time: Option<NaiveDateTime>,
+    pub room_conflicts: Vec<String>,
+    /// "staff (operation)" for staff also assigned elsewhere at the same time.
+    pub staff_conflicts: Vec<String>,
+}
@@ -0,0 +1,191 @@
+use super::{filter::{Filter, Filterable}, query_return::*};
+
+/// Rows a scope window's search box can filter.
+pub trait Searchable {
+    /// Text the search box is matched against.
+    fn search_fields(&self) -> Vec<&str>;
+}
+
+impl Searchable for PreOperativeDefault {
+    fn search_fields(&self) -> Vec<&str> {
+        vec![&self.op_label, &self.patient_full_name, &self.room_name]
+    }
+}
+impl Searchable for PreOperativeToolReady {
+    fn search_fields(&self) -> Vec<&str> {
+        vec![&self.equipment_name]
+    }
+}
+impl Searchable for InProgressDefault {
+    fn search_fields(&self) -> Vec<&str> {
+        vec![&self.op_label, &self.patient_full_name, &self.room_name]
+    }
+}
+impl Searchable for PostOperativeDefault {
+    fn search_fields(&self) -> Vec<&str> {
+        vec![&self.op_label, &self.patient_full_name, &self.room_name]
+    }
+}
+impl Searchable for RecoveryDefault {
+    fn search_fields(&self) -> Vec<&str> {
+        vec![&self.op_label, &self.patient_full_name, &self.ward_room_name]
+    }
+}
+impl Searchable for PatientOperation {
+    fn search_fields(&self) -> Vec<&str> {
+        vec![&self.op_label, &self.room_name]
+    }
+}
+impl Searchable for RoomOperation {
+    fn search_fields(&self) -> Vec<&str> {
+        vec![&self.op_label, &self.patient_full_name]
+    }
+}
+impl Searchable for CalendarBlock {
+    fn search_fields(&self) -> Vec<&str> {
+        vec![&self.op_label, &self.patient_full_name]
+    }
+}
+impl Searchable for ToolOperation {
+    fn search_fields(&self) -> Vec<&str> {
+        vec![&self.op_label, &self.room_name]
+    }
+}
+impl Searchable for TeamMember {
+    fn search_fields(&self) -> Vec<&str> {
+        vec![&self.full_name, &self.phone, &self.email]
+    }
+}
+impl Searchable for DischargeDefault {
+    fn search_fields(&self) -> Vec<&str> {
+        vec![&self.op_label, &self.patient_full_name, &self.room_name]
+    }
+}
+
+fn same_char(a: char, b: char) -> bool {
+    a == b || a.to_lowercase().eq(b.to_lowercase())
+}
+
+/// Char positions of `text` matched by `term`, ignoring case. A contiguous
+/// match is preferred; otherwise the chars of `term` may be spread out in order.
+pub fn fuzzy_match(text: &str, term: &str) -> Option<Vec<usize>> {
+    let text: Vec<char> = text.chars().collect();
+    let term: Vec<char> = term.chars().collect();
+    if term.is_empty() {
+        return Some(Vec::new());
+    }
+    if term.len() <= text.len() {
+        let contiguous = (0..=text.len() - term.len())
+            .find(|start| term.iter().zip(&text[*start..]).all(|(a, b)| same_char(*a, *b)));
+        if let Some(start) = contiguous {
+            return Some((start..start + term.len()).collect());
+        }
+    }
+    let mut positions = Vec::with_capacity(term.len());
+    let mut chars = text.iter().enumerate();
+    for wanted in term {
+        let (position, _) = chars.by_ref().find(|(_, c)| same_char(**c, wanted))?;
+        positions.push(position);
+    }
+    Some(positions)
+}
+
+/// Char positions of `text` to highlight for any of `terms`.
+pub fn highlighted(text: &str, terms: &[&str]) -> Vec<usize> {
+    let mut positions: Vec<usize> = terms.iter()
+        .filter_map(|term| fuzzy_match(text, term))
+        .flatten()
+        .collect();
+    positions.sort_unstable();
+    positions.dedup();
+    positions
+}
+
+fn retain<T: Filterable + Clone>(rows: &Option<Vec<T>>, filter: &Filter) -> Option<Vec<T>> {
+    rows.as_ref().map(|rows| rows.iter().filter(|row| filter.matches(*row)).cloned().collect())
+}
+
+impl WindowTable {
+    /// Copy of this view keeping only the rows matching `filter`.
+    pub fn filter(&self, filter: &Filter) -> WindowTable {
+        if filter.is_empty() {
+            return self.clone();
+        }
+        match self {
+            WindowTable::PreOperativeDefault(rows) => WindowTable::PreOperativeDefault(retain(rows, filter)),
+            WindowTable::PreOperativeToolReady(rows) => WindowTable::PreOperativeToolReady(retain(rows, filter)),
+            WindowTable::InProgressDefault(rows) => WindowTable::InProgressDefault(retain(rows, filter)),
+            WindowTable::PostOperativeDefault(rows) => WindowTable::PostOperativeDefault(retain(rows, filter)),
+            WindowTable::RecoveryDefault(rows) => WindowTable::RecoveryDefault(retain(rows, filter)),
+            WindowTable::DischargeDefault(rows) => WindowTable::DischargeDefault(retain(rows, filter)),
+            WindowTable::PatientData(patient) => WindowTable::PatientData(patient.as_ref().map(|patient| PatientData {
+                history: patient.history.iter().filter(|op| filter.matches(*op)).cloned().collect(),
+                ..patient.clone()
+            })),
+            WindowTable::RoomProperty(room) => WindowTable::RoomProperty(room.as_ref().map(|room| RoomProperty {
+                operations: room.operations.iter().filter(|op| filter.matches(*op)).cloned().collect(),
+                ..room.clone()
+            })),
+            WindowTable::OperationTeam(team) => WindowTable::OperationTeam(team.as_ref().map(|team| OperationTeam {
+                members: team.members.iter().filter(|member| filter.matches(*member)).cloned().collect(),
+                ..team.clone()
+            })),
+            WindowTable::ToolDetail(tool) => WindowTable::ToolDetail(tool.as_ref().map(|tool| ToolDetail {
+                operations: tool.operations.iter().filter(|op| filter.matches(*op)).cloned().collect(),
+                ..tool.clone()
+            })),
+            WindowTable::CalendarVisualization(lanes) => WindowTable::CalendarVisualization(lanes.as_ref().map(|lanes| {
+                lanes.iter()
+                    .map(|lane| CalendarLane {
+                        blocks: lane.blocks.iter().filter(|block| filter.matches(*block)).cloned().collect(),
+                        ..lane.clone()
+                    })
+                    .collect()
+            })),
+            WindowTable::StaffTimeline(lanes) => WindowTable::StaffTimeline(lanes.as_ref().map(|lanes| {
+                lanes.iter()
+                    .map(|lane| CalendarLane {
+                        blocks: lane.blocks.iter().filter(|block| filter.matches(*block)).cloned().collect(),
+                        ..lane.clone()
+                    })
+                    .collect()
+            })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contiguous_match_ignores_case() {
+        assert_eq!(fuzzy_match("Juan Dela Cruz", "dela"), Some(vec![5, 6, 7, 8]));
+    }
+
+    #[test]
+    fn contiguous_match_is_preferred_over_a_spread_one() {
+        assert_eq!(fuzzy_match("a-b-ab", "ab"), Some(vec![4, 5]));
+    }
+
+    #[test]
+    fn spread_match_keeps_the_order_of_the_term() {
+        assert_eq!(fuzzy_match("appendectomy", "apy"), Some(vec![0, 1, 11]));
+        assert_eq!(fuzzy_match("appendectomy", "yap"), None);
+    }
+
+    #[test]
+    fn empty_term_matches_without_positions() {
+        assert_eq!(fuzzy_match("anything", ""), Some(Vec::new()));
+    }
+
+    #[test]
+    fn term_longer_than_text_does_not_match() {
+        assert_eq!(fuzzy_match("or", "or-2"), None);
+    }
+
+    #[test]
+    fn highlighted_merges_the_positions_of_every_term() {
+        assert_eq!(highlighted("OR-2", &["or", "r-"]), vec![0, 1, 2]);
+    }
+}
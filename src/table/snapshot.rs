@@ -0,0 +1,160 @@
+use std::fmt;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::cipher;
+
+use super::{TableData, SECRET_COLUMNS};
+
+/// eframe storage key of the last good snapshot.
+pub const STORAGE_KEY: &str = "table_data_snapshot";
+
+/// A row that was left out of a snapshot because it did not parse.
+#[derive(Debug, Clone)]
+pub struct RowError {
+    pub table: &'static str,
+    pub row: usize,
+    pub id: Option<i64>,
+    pub message: String,
+}
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.id {
+            Some(id) => write!(f, "{} row {} (id {}): {}", self.table, self.row, id, self.message),
+            None => write!(f, "{} row {}: {}", self.table, self.row, self.message),
+        }
+    }
+}
+
+/// What happened while loading an `Initialize` snapshot.
+#[derive(Debug, Clone, Default)]
+pub struct LoadReport {
+    pub rejected: Vec<RowError>,
+}
+impl LoadReport {
+    pub fn is_clean(&self) -> bool {
+        self.rejected.is_empty()
+    }
+}
+
+/// The snapshot could not be used at all; the previous data is kept.
+#[derive(Debug)]
+pub enum SnapshotError {
+    Malformed(serde_json::Error),
+    Table {
+        table: &'static str,
+        error: serde_json::Error,
+    },
+}
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Malformed(error) => write!(f, "snapshot is not a table map: {}", error),
+            SnapshotError::Table { table, error } => write!(f, "{} is not a list of rows: {}", table, error),
+        }
+    }
+}
+
+/// The last good `TableData`, kept encrypted on disk so the desk can start
+/// while the server is unreachable.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct StoredSnapshot {
+    /// Unix seconds of the moment the data was last known to be live.
+    pub saved_at: i64,
+    pub data: TableData,
+}
+impl StoredSnapshot {
+    pub fn new(data: TableData) -> Self {
+        StoredSnapshot { saved_at: Utc::now().timestamp(), data }
+    }
+    pub fn saved_at(&self) -> DateTime<Utc> {
+        DateTime::from_timestamp(self.saved_at, 0).unwrap_or_default()
+    }
+    /// Encrypts the snapshot without `SECRET_COLUMNS`; the key ships with
+    /// the app, so the seal alone does not keep them safe.
+    pub fn seal(&self) -> Result<String, &'static str> {
+        let mut json = serde_json::to_value(self).map_err(|_| "err serializing snapshot")?;
+        for (table, column) in SECRET_COLUMNS {
+            let rows = json.get_mut("data").and_then(|data| data.get_mut(*table)).and_then(serde_json::Value::as_array_mut);
+            for row in rows.into_iter().flatten() {
+                if let Some(row) = row.as_object_mut() {
+                    row.remove(*column);
+                }
+            }
+        }
+        cipher::seal(&json.to_string())
+    }
+    pub fn unseal(sealed: &str) -> Result<Self, &'static str> {
+        let json = cipher::unseal(sealed)?;
+        serde_json::from_str(&json).map_err(|_| "err parsing stored snapshot")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{database::table::{Frontdesk, Operation}, table::AnyTable};
+
+    use super::*;
+
+    #[test]
+    fn rows_that_do_not_parse_are_rejected_and_the_rest_kept() {
+        let data = TableData::new();
+        let mut report = LoadReport::default();
+        let rows = json!([
+            { "id": 1, "label": "APP" },
+            { "id": 2, "start_time": "soon" },
+            { "label": 7 },
+        ]);
+        data.operation.initialize_json(rows, &mut report).unwrap();
+        assert_eq!(data.operation.read().iter().filter_map(|op| op.id).collect::<Vec<_>>(), vec![1]);
+        let rejected: Vec<(&str, usize, Option<i64>)> = report.rejected.iter().map(|error| (error.table, error.row, error.id)).collect();
+        assert_eq!(rejected, vec![("operation", 1, Some(2)), ("operation", 2, None)]);
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn a_clean_snapshot_replaces_every_table() {
+        let data = TableData::new();
+        data.operation.update(9, Operation { id: Some(9), ..Operation::default() });
+        let report = data.initialize(json!({ "operation": [{ "id": 1, "label": "APP" }] }).to_string()).unwrap();
+        assert!(report.is_clean());
+        assert!(data.operation.read().get(9).is_none());
+        assert!(data.operation.read().get(1).is_some());
+    }
+
+    #[test]
+    fn a_table_that_is_not_a_list_keeps_the_previous_data() {
+        let data = TableData::new();
+        data.operation.update(9, Operation { id: Some(9), ..Operation::default() });
+        let result = data.initialize(json!({ "operation": [{ "id": 1 }], "staff": { "id": 1 } }).to_string());
+        assert!(matches!(result, Err(SnapshotError::Table { table: "staff", .. })));
+        assert!(data.operation.read().get(9).is_some());
+        assert!(data.operation.read().get(1).is_none());
+        assert!(matches!(data.initialize("not json".to_string()), Err(SnapshotError::Malformed(_))));
+    }
+
+    #[test]
+    fn secrets_are_not_stored() {
+        let data = TableData::new();
+        data.frontdesk.update(1, Frontdesk {
+            id: Some(1),
+            name: Some("desk".to_string()),
+            password: Some("hunter2".to_string()),
+            session_token: Some("token".to_string()),
+            ..Frontdesk::default()
+        });
+        let sealed = StoredSnapshot::new(data.clone()).seal().unwrap();
+        let stored = StoredSnapshot::unseal(&sealed).unwrap();
+        let frontdesk = stored.data.frontdesk.read();
+        let desk = frontdesk.get(1).unwrap();
+        assert_eq!(desk.name.as_deref(), Some("desk"));
+        assert_eq!(desk.password, None);
+        assert_eq!(desk.session_token, None);
+        assert!(!cipher::unseal(&sealed).unwrap().contains("hunter2"));
+        // the live rows keep their secrets
+        assert_eq!(data.frontdesk.read().get(1).and_then(|desk| desk.password.clone()).as_deref(), Some("hunter2"));
+    }
+}
@@ -0,0 +1,241 @@
+use std::cmp::Ordering;
+
+use chrono::NaiveDateTime;
+
+use crate::local_now;
+
+use super::query_return::*;
+
+/// Column a scope window is sorted by, named by its heading.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sort {
+    pub column: &'static str,
+    pub descending: bool,
+}
+impl Sort {
+    /// Sort after clicking `column`: ascending first, then flipping.
+    pub fn toggle(current: &Option<Sort>, column: &'static str) -> Sort {
+        match current {
+            Some(sort) if sort.column == column => Sort { column, descending: !sort.descending },
+            _ => Sort { column, descending: false },
+        }
+    }
+    pub fn indicator(current: &Option<Sort>, column: &str) -> &'static str {
+        match current {
+            Some(sort) if sort.column == column && sort.descending => "⏷",
+            Some(sort) if sort.column == column => "⏶",
+            _ => "",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+pub enum SortValue {
+    /// Lower-cased so sorting ignores case.
+    Text(String),
+    Number(f64),
+    Time(Option<NaiveDateTime>),
+}
+fn text(value: &str) -> Option<SortValue> {
+    Some(SortValue::Text(value.to_lowercase()))
+}
+
+/// Rows whose columns can be sorted by heading.
+pub trait Sortable {
+    fn sort_value(&self, column: &str) -> Option<SortValue>;
+}
+
+impl Sortable for PreOperativeDefault {
+    fn sort_value(&self, column: &str) -> Option<SortValue> {
+        match column {
+            "LABEL" => text(&self.op_label),
+            "PATIENT FULL NAME" => text(&self.patient_full_name),
+            "ROOM NAME" => text(&self.room_name),
+            "READINESS" => Some(SortValue::Number(self.readiness.missing.len() as f64)),
+            "TOOLS READY" => Some(SortValue::Number(self.on_site_percentage)),
+            "STARTING OPERATION" => Some(SortValue::Time(self.start_time)),
+            "ENDING OPERATION" => Some(SortValue::Time(self.end_time)),
+            _ => None,
+        }
+    }
+}
+impl Sortable for PreOperativeToolReady {
+    fn sort_value(&self, column: &str) -> Option<SortValue> {
+        match column {
+            "equipment name" => text(&self.equipment_name),
+            "equipment on site" => Some(SortValue::Number(if self.on_site { 1.0 } else { 0.0 })),
+            "tool status" => text(&self.tool_status.to_string()),
+            _ => None,
+        }
+    }
+}
+impl Sortable for InProgressDefault {
+    fn sort_value(&self, column: &str) -> Option<SortValue> {
+        match column {
+            "LABEL" => text(&self.op_label),
+            "PATIENT FULL NAME" => text(&self.patient_full_name),
+            "ROOM NAME" => text(&self.room_name),
+            "STAFF" => Some(SortValue::Number(self.staff_count as f64)),
+            "ELAPSED / PLANNED" => self.progress(local_now()).map(|progress| SortValue::Number(progress.into())),
+            "ENDING OPERATION" => Some(SortValue::Time(self.end_time)),
+            _ => None,
+        }
+    }
+}
+impl Sortable for PostOperativeDefault {
+    fn sort_value(&self, column: &str) -> Option<SortValue> {
+        match column {
+            "LABEL" => text(&self.op_label),
+            "PATIENT FULL NAME" => text(&self.patient_full_name),
+            "ROOM NAME" => text(&self.room_name),
+            "STARTING OPERATION" => Some(SortValue::Time(self.start_time)),
+            "ENDING OPERATION" => Some(SortValue::Time(self.end_time)),
+            _ => None,
+        }
+    }
+}
+impl Sortable for RecoveryDefault {
+    fn sort_value(&self, column: &str) -> Option<SortValue> {
+        match column {
+            "LABEL" => text(&self.op_label),
+            "PATIENT FULL NAME" => text(&self.patient_full_name),
+            "WARD ROOM" => text(&self.ward_room_name),
+            "ASSISTANTS" => text(&self.assistant_names),
+            "OPERATION ENDED" => Some(SortValue::Time(self.end_time)),
+            _ => None,
+        }
+    }
+}
+impl Sortable for DischargeDefault {
+    fn sort_value(&self, column: &str) -> Option<SortValue> {
+        match column {
+            "LABEL" => text(&self.op_label),
+            "PATIENT FULL NAME" => text(&self.patient_full_name),
+            "PHONE" => text(&self.patient_phone),
+            "ROOM NAME" => text(&self.room_name),
+            "DISCHARGED" => Some(SortValue::Time(self.end_time)),
+            _ => None,
+        }
+    }
+}
+impl Sortable for PatientOperation {
+    fn sort_value(&self, column: &str) -> Option<SortValue> {
+        match column {
+            "LABEL" => text(&self.op_label),
+            "STATUS" => text(&self.op_status.as_ref().map(|status| format!("{:?}", status)).unwrap_or_default()),
+            "ROOM NAME" => text(&self.room_name),
+            "STARTING OPERATION" => Some(SortValue::Time(self.start_time)),
+            "ENDING OPERATION" => Some(SortValue::Time(self.end_time)),
+            _ => None,
+        }
+    }
+}
+impl Sortable for RoomOperation {
+    fn sort_value(&self, column: &str) -> Option<SortValue> {
+        match column {
+            "LABEL" => text(&self.op_label),
+            "STATUS" => text(&self.op_status.as_ref().map(|status| format!("{:?}", status)).unwrap_or_default()),
+            "PATIENT FULL NAME" => text(&self.patient_full_name),
+            "STARTING OPERATION" => Some(SortValue::Time(self.start_time)),
+            "ENDING OPERATION" => Some(SortValue::Time(self.end_time)),
+            _ => None,
+        }
+    }
+}
+
+impl Sortable for ToolOperation {
+    fn sort_value(&self, column: &str) -> Option<SortValue> {
+        match column {
+            "LABEL" => text(&self.op_label),
+            "STATUS" => text(&self.op_status.as_ref().map(|status| format!("{:?}", status)).unwrap_or_default()),
+            "ROOM NAME" => text(&self.room_name),
+            "ON SITE" => Some(SortValue::Number(if self.on_site { 1.0 } else { 0.0 })),
+            "STARTING OPERATION" => Some(SortValue::Time(self.start_time)),
+            "ENDING OPERATION" => Some(SortValue::Time(self.end_time)),
+            _ => None,
+        }
+    }
+}
+impl Sortable for TeamMember {
+    fn sort_value(&self, column: &str) -> Option<SortValue> {
+        match column {
+            "FULL NAME" => text(&self.full_name),
+            "ROLE" => text(&self.role.as_ref().map(|role| format!("{:?}", role)).unwrap_or_default()),
+            "PHONE" => text(&self.phone),
+            "EMAIL" => text(&self.email),
+            _ => None,
+        }
+    }
+}
+
+fn sort_rows<T: Sortable>(rows: &mut [T], sort: &Sort) {
+    rows.sort_by(|a, b| {
+        let ordering = a.sort_value(sort.column)
+            .partial_cmp(&b.sort_value(sort.column))
+            .unwrap_or(Ordering::Equal);
+        if sort.descending { ordering.reverse() } else { ordering }
+    });
+}
+
+impl WindowTable {
+    /// Orders the rows by `sort`. A column this view does not have leaves it as is.
+    pub fn sort(&mut self, sort: &Sort) {
+        match self {
+            WindowTable::PreOperativeDefault(Some(rows)) => sort_rows(rows, sort),
+            WindowTable::PreOperativeToolReady(Some(rows)) => sort_rows(rows, sort),
+            WindowTable::InProgressDefault(Some(rows)) => sort_rows(rows, sort),
+            WindowTable::PostOperativeDefault(Some(rows)) => sort_rows(rows, sort),
+            WindowTable::RecoveryDefault(Some(rows)) => sort_rows(rows, sort),
+            WindowTable::DischargeDefault(Some(rows)) => sort_rows(rows, sort),
+            WindowTable::PatientData(Some(patient)) => sort_rows(&mut patient.history, sort),
+            WindowTable::RoomProperty(Some(room)) => sort_rows(&mut room.operations, sort),
+            WindowTable::OperationTeam(Some(team)) => sort_rows(&mut team.members, sort),
+            WindowTable::ToolDetail(Some(tool)) => sort_rows(&mut tool.operations, sort),
+            _ => {},
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    fn in_progress(label: &str, started_minutes_ago: i64, planned_minutes: i64) -> InProgressDefault {
+        let start_time = local_now() - Duration::minutes(started_minutes_ago);
+        InProgressDefault {
+            op_id: None,
+            op_label: label.to_string(),
+            patient_full_name: String::new(),
+            room_name: String::new(),
+            staff_names: String::new(),
+            staff_count: 0,
+            staff_conflicts: Vec::new(),
+            start_time: Some(start_time),
+            end_time: Some(start_time + Duration::minutes(planned_minutes)),
+        }
+    }
+
+    fn labels(rows: &[InProgressDefault]) -> Vec<&str> {
+        rows.iter().map(|row| row.op_label.as_str()).collect()
+    }
+
+    #[test]
+    fn ascending_progress_puts_the_least_elapsed_first() {
+        // the longest running one is the least through its plan
+        let mut rows = vec![in_progress("overrun", 90, 60), in_progress("long", 120, 600), in_progress("half", 30, 60)];
+        sort_rows(&mut rows, &Sort { column: "ELAPSED / PLANNED", descending: false });
+        assert_eq!(labels(&rows), vec!["long", "half", "overrun"]);
+        sort_rows(&mut rows, &Sort { column: "ELAPSED / PLANNED", descending: true });
+        assert_eq!(labels(&rows), vec!["overrun", "half", "long"]);
+    }
+
+    #[test]
+    fn toggling_a_column_flips_it() {
+        let sort = Sort::toggle(&None, "LABEL");
+        assert!(!sort.descending);
+        assert!(Sort::toggle(&Some(sort.clone()), "LABEL").descending);
+        assert!(!Sort::toggle(&Some(sort), "ROOM NAME").descending);
+    }
+}
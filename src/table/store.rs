@@ -0,0 +1,326 @@
+use std::{any::Any, collections::HashMap, fmt, ops::Deref, sync::{atomic::{AtomicU64, Ordering}, Arc, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard}};
+
+use serde::{de::DeserializeOwned, Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::TableTarget;
+
+use super::{snapshot::{LoadReport, RowError, SnapshotError}, update::{DeleteRow, UpdateRow}};
+
+/// A row type the server sends us. Everything `Table` does is written
+/// against this trait, so a new table only needs a struct and an impl.
+pub trait Entity: Clone + Default + fmt::Debug + Serialize + DeserializeOwned + Send + Sync + 'static {
+    const TARGET: TableTarget;
+    /// Key of the table in the `Initialize` snapshot.
+    const NAME: &'static str;
+    fn id(&self) -> Option<i32>;
+    /// Ids this row points at in other tables.
+    fn foreign_keys(&self) -> Vec<ForeignKey> {
+        Vec::new()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ForeignKey {
+    pub column: &'static str,
+    pub target: TableTarget,
+    pub id: Option<i32>,
+}
+
+#[derive(Debug)]
+pub struct Rows<T> {
+    rows: Vec<T>,
+    index: HashMap<i32, usize>,
+}
+impl<T: Entity> Rows<T> {
+    fn new(rows: Vec<T>) -> Self {
+        let mut rows = Rows { rows, index: HashMap::new() };
+        rows.reindex();
+        rows
+    }
+    fn reindex(&mut self) {
+        self.index = self.rows.iter()
+            .enumerate()
+            .filter_map(|(position, row)| row.id().map(|id| (id, position)))
+            .collect();
+    }
+    pub fn get(&self, id: i32) -> Option<&T> {
+        self.index.get(&id).map(|position| &self.rows[*position])
+    }
+}
+impl<T> Deref for Rows<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        &self.rows
+    }
+}
+
+#[derive(Debug)]
+pub struct Table<T> {
+    rows: Arc<RwLock<Rows<T>>>,
+    /// Bumped on every change so views know when to re-run their query.
+    version: Arc<AtomicU64>,
+}
+impl<T> Clone for Table<T> {
+    fn clone(&self) -> Self {
+        Table { rows: Arc::clone(&self.rows), version: Arc::clone(&self.version) }
+    }
+}
+impl<T: Entity> Default for Table<T> {
+    fn default() -> Self {
+        Table::from_rows(Vec::new())
+    }
+}
+impl<T: Entity> Table<T> {
+    fn from_rows(rows: Vec<T>) -> Self {
+        Table { rows: Arc::new(RwLock::new(Rows::new(rows))), version: Arc::new(AtomicU64::new(0)) }
+    }
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::Acquire)
+    }
+    fn changed(&self) {
+        self.version.fetch_add(1, Ordering::AcqRel);
+    }
+    pub fn read(&self) -> RwLockReadGuard<'_, Rows<T>> {
+        self.rows.read().unwrap_or_else(PoisonError::into_inner)
+    }
+    fn write(&self) -> RwLockWriteGuard<'_, Rows<T>> {
+        self.rows.write().unwrap_or_else(PoisonError::into_inner)
+    }
+    pub fn initialize(&self, rows: Vec<T>) {
+        *self.write() = Rows::new(rows);
+        self.changed();
+    }
+    /// Replaces the row with `id`, or appends it when we have never seen it.
+    /// Returns the row that was replaced.
+    pub fn update(&self, id: i32, new_row_data: T) -> Option<T> {
+        let mut rows = self.write();
+        let previous = if let Some(&position) = rows.index.get(&id) {
+            Some(std::mem::replace(&mut rows.rows[position], new_row_data))
+        } else {
+            rows.rows.push(new_row_data);
+            None
+        };
+        rows.reindex();
+        drop(rows);
+        self.changed();
+        previous
+    }
+    pub fn insert(&self, row: T) -> Option<T> {
+        if let Some(id) = row.id() {
+            self.update(id, row)
+        } else {
+            self.write().rows.push(row);
+            self.changed();
+            None
+        }
+    }
+    pub fn delete(&self, id: i32) -> Option<T> {
+        let mut rows = self.write();
+        let position = rows.index.get(&id).copied()?;
+        let removed = rows.rows.remove(position);
+        rows.reindex();
+        drop(rows);
+        self.changed();
+        Some(removed)
+    }
+}
+impl<T: Entity> Serialize for Table<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.read().rows.serialize(serializer)
+    }
+}
+impl<'de, T: Entity> Deserialize<'de> for Table<T> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let rows = Vec::<T>::deserialize(deserializer)?;
+        Ok(Table::from_rows(rows))
+    }
+}
+
+/// Type-erased view of a `Table` so `TableData` can route a message by its
+/// `TableTarget` without naming every entity.
+pub trait AnyTable {
+    fn target(&self) -> TableTarget;
+    fn name(&self) -> &'static str;
+    fn version(&self) -> u64;
+    fn as_any(&self) -> &dyn Any;
+    /// Loads rows into this table, skipping and reporting the ones that do not parse.
+    fn initialize_json(&self, value: serde_json::Value, report: &mut LoadReport) -> Result<(), SnapshotError>;
+    /// Takes over the rows of a table of the same type that was loaded on the side.
+    fn adopt(&self, staged: &dyn AnyTable);
+    fn len(&self) -> usize;
+    fn contains(&self, id: i32) -> bool;
+    /// Id and foreign keys of every row, in table order.
+    fn keys(&self) -> Vec<(Option<i32>, Vec<ForeignKey>)>;
+    /// Field names of a row as they appear in the snapshot.
+    #[cfg(feature = "sql-mirror")]
+    fn columns(&self) -> Vec<String>;
+    fn rows_json(&self) -> Vec<serde_json::Value>;
+    fn update_json(&self, raw_string: &str) -> Result<(), serde_json::Error>;
+    fn insert_json(&self, raw_string: &str) -> Result<(), serde_json::Error>;
+    fn delete_json(&self, raw_string: &str) -> Result<(), serde_json::Error>;
+}
+impl<T: Entity> AnyTable for Table<T> {
+    fn target(&self) -> TableTarget {
+        T::TARGET
+    }
+    fn name(&self) -> &'static str {
+        T::NAME
+    }
+    fn version(&self) -> u64 {
+        Table::version(self)
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn initialize_json(&self, value: serde_json::Value, report: &mut LoadReport) -> Result<(), SnapshotError> {
+        let raw_rows: Vec<serde_json::Value> = serde_json::from_value(value)
+            .map_err(|error| SnapshotError::Table { table: T::NAME, error })?;
+        let rows = raw_rows.into_iter()
+            .enumerate()
+            .filter_map(|(position, raw_row)| {
+                let id = raw_row.get("id").and_then(serde_json::Value::as_i64);
+                match serde_json::from_value::<T>(raw_row) {
+                    Ok(row) => Some(row),
+                    Err(err) => {
+                        report.rejected.push(RowError {
+                            table: T::NAME,
+                            row: position,
+                            id,
+                            message: err.to_string(),
+                        });
+                        None
+                    },
+                }
+            })
+            .collect();
+        self.initialize(rows);
+        Ok(())
+    }
+    fn adopt(&self, staged: &dyn AnyTable) {
+        if let Some(staged) = staged.as_any().downcast_ref::<Table<T>>() {
+            let rows = std::mem::take(&mut staged.write().rows);
+            self.initialize(rows);
+        }
+    }
+    fn len(&self) -> usize {
+        self.read().len()
+    }
+    fn contains(&self, id: i32) -> bool {
+        self.read().get(id).is_some()
+    }
+    fn keys(&self) -> Vec<(Option<i32>, Vec<ForeignKey>)> {
+        self.read().iter().map(|row| (row.id(), row.foreign_keys())).collect()
+    }
+    #[cfg(feature = "sql-mirror")]
+    fn columns(&self) -> Vec<String> {
+        match serde_json::to_value(T::default()) {
+            Ok(serde_json::Value::Object(fields)) => fields.keys().cloned().collect(),
+            _ => Vec::new(),
+        }
+    }
+    fn rows_json(&self) -> Vec<serde_json::Value> {
+        self.read().iter()
+            .filter_map(|row| serde_json::to_value(row).ok())
+            .collect()
+    }
+    fn update_json(&self, raw_string: &str) -> Result<(), serde_json::Error> {
+        let update_row: UpdateRow<T> = serde_json::from_str(raw_string)?;
+        self.update(update_row.id as i32, update_row.new_row_data);
+        Ok(())
+    }
+    fn insert_json(&self, raw_string: &str) -> Result<(), serde_json::Error> {
+        let row: T = serde_json::from_str(raw_string)?;
+        self.insert(row);
+        Ok(())
+    }
+    fn delete_json(&self, raw_string: &str) -> Result<(), serde_json::Error> {
+        let delete_row: DeleteRow = serde_json::from_str(raw_string)?;
+        self.delete(delete_row.id as i32);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use crate::{database::table::Operation, table::TableData};
+
+    use super::*;
+
+    fn operation(id: i32, label: &str) -> Operation {
+        Operation { id: Some(id), label: Some(label.to_string()), ..Operation::default() }
+    }
+
+    fn label(table: &Table<Operation>, id: i32) -> Option<String> {
+        table.read().get(id).and_then(|op| op.label.clone())
+    }
+
+    #[test]
+    fn insert_update_and_delete_bump_the_version() {
+        let table: Table<Operation> = Table::default();
+        assert_eq!(table.version(), 0);
+        assert!(table.insert(operation(1, "APP")).is_none());
+        assert_eq!(table.version(), 1);
+        let replaced = table.update(1, operation(1, "CHOLE"));
+        assert_eq!(replaced.and_then(|op| op.label).as_deref(), Some("APP"));
+        assert_eq!(label(&table, 1).as_deref(), Some("CHOLE"));
+        assert_eq!(table.version(), 2);
+        assert_eq!(table.delete(1).and_then(|op| op.label).as_deref(), Some("CHOLE"));
+        assert!(table.read().get(1).is_none());
+        assert_eq!(table.version(), 3);
+    }
+
+    #[test]
+    fn deleting_an_unknown_id_changes_nothing() {
+        let table: Table<Operation> = Table::default();
+        table.insert(operation(1, "APP"));
+        assert!(table.delete(2).is_none());
+        assert_eq!(table.version(), 1);
+        assert_eq!(table.read().len(), 1);
+    }
+
+    #[test]
+    fn rows_stay_indexed_after_a_delete() {
+        let table: Table<Operation> = Table::default();
+        for (id, name) in [(1, "APP"), (2, "CHOLE"), (3, "HERNIA")] {
+            table.insert(operation(id, name));
+        }
+        table.delete(1);
+        assert_eq!(label(&table, 2).as_deref(), Some("CHOLE"));
+        assert_eq!(label(&table, 3).as_deref(), Some("HERNIA"));
+    }
+
+    #[test]
+    fn clones_share_rows_and_version() {
+        let table: Table<Operation> = Table::default();
+        let clone = table.clone();
+        clone.insert(operation(1, "APP"));
+        assert_eq!(label(&table, 1).as_deref(), Some("APP"));
+        assert_eq!(table.version(), 1);
+    }
+
+    #[test]
+    fn json_messages_go_through_the_same_store() {
+        let table: Table<Operation> = Table::default();
+        table.insert_json(&json!({ "id": 1, "label": "APP" }).to_string()).unwrap();
+        table.update_json(&json!({ "id": 1, "new_row_data": { "id": 1, "label": "CHOLE" } }).to_string()).unwrap();
+        assert_eq!(label(&table, 1).as_deref(), Some("CHOLE"));
+        table.delete_json(&json!({ "id": 1 }).to_string()).unwrap();
+        assert_eq!(AnyTable::len(&table), 0);
+        assert_eq!(AnyTable::version(&table), 3);
+        assert!(table.update_json("{ \"id\": 1 }").is_err());
+    }
+
+    #[test]
+    fn versions_move_only_for_the_changed_table() {
+        let data = TableData::new();
+        let targets = [TableTarget::Operation, TableTarget::Patient, TableTarget::Room];
+        let before = data.versions(&targets);
+        data.operation.insert(operation(1, "APP"));
+        let after = data.versions(&targets);
+        assert_eq!(after[0], before[0] + 1);
+        assert_eq!(after[1..], before[1..]);
+    }
+}
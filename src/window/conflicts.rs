@@ -0,0 +1,168 @@
+use egui::{Color32, Context, RichText};
+use egui_extras::{Column, TableBuilder};
+
+use crate::{format_date, table::{conflict::ToolConflict, TableData}};
+
+#[derive(Default, Debug, Clone)]
+pub struct ConflictsWindow {
+    pub show: bool,
+}
+
+/// Lists every double-booked room, member of staff and tool. Returns the operation the desk clicked.
+pub fn show(ctx: &Context, window: &mut ConflictsWindow, data: &TableData) -> Option<i32> {
+    let report = data.conflicts();
+    let mut opened = None;
+    egui::Window::new("⚠ Conflicts")
+        .id(egui::Id::new("conflicts"))
+        .open(&mut window.show)
+        .resizable(true)
+        .collapsible(true)
+        .show(ctx, |ui| {
+            if report.is_empty() {
+                ui.label(RichText::new("✔ no conflicts found").color(Color32::from_rgb(0, 140, 26)));
+                return;
+            }
+            let operations = data.operation.read();
+            let rooms = data.room.read();
+            let staff = data.staff.read();
+            let tools = data.tool.read();
+            let equipment = data.equipment.read();
+            let label_of = |id: i32| operations.get(id).and_then(|op| op.label.clone()).unwrap_or_else(|| format!("#{}", id));
+            let times_of = |id: i32| operations.get(id)
+                .map(|op| format!("{} → {}", format_date(op.start_time), format_date(op.end_time)))
+                .unwrap_or_default();
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                if !report.rooms.is_empty() {
+                    ui.heading(format!("double-booked rooms ({})", report.rooms.len()));
+                    ui.push_id("room_conflicts", |ui| {
+                        TableBuilder::new(ui)
+                            .striped(true)
+                            .column(Column::auto())
+                            .column(Column::auto())
+                            .column(Column::auto())
+                            .column(Column::remainder())
+                            .header(20.0, |mut header| {
+                                for title in ["ROOM", "OPERATION", "OPERATION", "OVERLAP"] {
+                                    header.col(|ui| {
+                                        ui.strong(title);
+                                    });
+                                }
+                            })
+                            .body(|mut body| {
+                                for conflict in &report.rooms {
+                                    body.row(36.0, |mut row| {
+                                        row.col(|ui| {
+                                            ui.label(rooms.get(conflict.room_id).and_then(|room| room.name.clone()).unwrap_or_else(|| format!("#{}", conflict.room_id)));
+                                        });
+                                        for op_id in [conflict.first, conflict.second] {
+                                            row.col(|ui| {
+                                                ui.vertical(|ui| {
+                                                    if ui.link(label_of(op_id)).clicked() {
+                                                        opened = Some(op_id);
+                                                    }
+                                                    ui.weak(times_of(op_id));
+                                                });
+                                            });
+                                        }
+                                        row.col(|ui| {
+                                            ui.label(format!("{} → {}", format_date(Some(conflict.overlap_start)), format_date(Some(conflict.overlap_end))));
+                                        });
+                                    });
+                                }
+                            });
+                    });
+                }
+                if !report.staff.is_empty() {
+                    ui.heading(format!("double-booked staff ({})", report.staff.len()));
+                    ui.push_id("staff_conflicts", |ui| {
+                        TableBuilder::new(ui)
+                            .striped(true)
+                            .column(Column::auto())
+                            .column(Column::auto())
+                            .column(Column::auto())
+                            .column(Column::remainder())
+                            .header(20.0, |mut header| {
+                                for title in ["STAFF", "OPERATION", "OPERATION", "OVERLAP"] {
+                                    header.col(|ui| {
+                                        ui.strong(title);
+                                    });
+                                }
+                            })
+                            .body(|mut body| {
+                                for conflict in &report.staff {
+                                    body.row(36.0, |mut row| {
+                                        row.col(|ui| {
+                                            ui.label(staff.get(conflict.staff_id)
+                                                .map(|s| s.full_name())
+                                                .unwrap_or_else(|| format!("#{}", conflict.staff_id)));
+                                        });
+                                        for op_id in [conflict.first, conflict.second] {
+                                            row.col(|ui| {
+                                                ui.vertical(|ui| {
+                                                    if ui.link(label_of(op_id)).clicked() {
+                                                        opened = Some(op_id);
+                                                    }
+                                                    ui.weak(times_of(op_id));
+                                                });
+                                            });
+                                        }
+                                        row.col(|ui| {
+                                            ui.label(format!("{} → {}", format_date(Some(conflict.overlap_start)), format_date(Some(conflict.overlap_end))));
+                                        });
+                                    });
+                                }
+                            });
+                    });
+                }
+                if !report.tools.is_empty() {
+                    ui.heading(format!("unavailable tools ({})", report.tools.len()));
+                    ui.push_id("tool_conflicts", |ui| {
+                        TableBuilder::new(ui)
+                            .striped(true)
+                            .column(Column::auto())
+                            .column(Column::auto())
+                            .column(Column::remainder())
+                            .header(20.0, |mut header| {
+                                for title in ["TOOL", "OPERATIONS", "PROBLEM"] {
+                                    header.col(|ui| {
+                                        ui.strong(title);
+                                    });
+                                }
+                            })
+                            .body(|mut body| {
+                                for conflict in &report.tools {
+                                    let tool_id = conflict.tool_id();
+                                    let (op_ids, problem) = match conflict {
+                                        ToolConflict::DoubleBooked { first, second, overlap_start, overlap_end, .. } => (
+                                            vec![*first, *second],
+                                            format!("in both operations {} → {}", format_date(Some(*overlap_start)), format_date(Some(*overlap_end))),
+                                        ),
+                                        ToolConflict::Borrowed { op_id, .. } => (vec![*op_id], "borrowed".to_string()),
+                                    };
+                                    body.row(20.0 * op_ids.len() as f32 + 4.0, |mut row| {
+                                        row.col(|ui| {
+                                            ui.label(tools.get(tool_id).and_then(|tool| tool.info_id).and_then(|id| equipment.get(id))
+                                                .and_then(|equipment| equipment.name.clone())
+                                                .map_or_else(|| format!("tool #{}", tool_id), |name| format!("{} #{}", name, tool_id)));
+                                        });
+                                        row.col(|ui| {
+                                            ui.vertical(|ui| {
+                                                for op_id in op_ids {
+                                                    if ui.link(label_of(op_id)).on_hover_text(times_of(op_id)).clicked() {
+                                                        opened = Some(op_id);
+                                                    }
+                                                }
+                                            });
+                                        });
+                                        row.col(|ui| {
+                                            ui.label(problem);
+                                        });
+                                    });
+                                }
+                            });
+                    });
+                }
+            });
+        });
+    opened
+}
@@ -0,0 +1,87 @@
+use egui::{Color32, Context, RichText, Ui};
+use serde_json::Value;
+
+use crate::{format_date, table::diff::SnapshotDiff};
+
+const ADDED: Color32 = Color32::from_rgb(0, 140, 26);
+const REMOVED: Color32 = Color32::from_rgb(255, 46, 32);
+const CHANGED: Color32 = Color32::from_rgb(246, 140, 46);
+
+#[derive(Default, Debug, Clone)]
+pub struct SnapshotDiffWindow {
+    pub show: bool,
+    /// Index into the diff history, newest last.
+    pub selected: Option<usize>,
+}
+
+pub fn show(ctx: &Context, window: &mut SnapshotDiffWindow, diffs: &[SnapshotDiff]) {
+    let SnapshotDiffWindow { show, selected } = window;
+    egui::Window::new("🔀 Snapshot changes")
+        .id(egui::Id::new("snapshot_diff"))
+        .open(show)
+        .resizable(true)
+        .collapsible(true)
+        .show(ctx, |ui| {
+            if diffs.is_empty() {
+                ui.label("No re-initialize has changed any rows yet.");
+                return;
+            }
+            ui.horizontal_wrapped(|ui| {
+                for (position, diff) in diffs.iter().enumerate().rev() {
+                    let received_at = format_date(Some((diff.received_at + chrono::Duration::hours(8)).naive_utc()));
+                    let is_selected = selected.unwrap_or(diffs.len() - 1) == position;
+                    if ui.selectable_label(is_selected, received_at).clicked() {
+                        *selected = Some(position);
+                    }
+                }
+            });
+            ui.separator();
+            let Some(diff) = diffs.get(selected.unwrap_or(diffs.len() - 1)) else {
+                return;
+            };
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                for table_diff in &diff.tables {
+                    let title = format!(
+                        "{}  +{} −{} ~{}",
+                        table_diff.table,
+                        table_diff.added.len(),
+                        table_diff.removed.len(),
+                        table_diff.changed.len()
+                    );
+                    ui.collapsing(title, |ui| {
+                        for row in &table_diff.added {
+                            row_label(ui, "+", row, ADDED);
+                        }
+                        for row in &table_diff.removed {
+                            row_label(ui, "−", row, REMOVED);
+                        }
+                        for row_change in &table_diff.changed {
+                            ui.collapsing(RichText::new(format!("~ id {}", row_change.id)).color(CHANGED), |ui| {
+                                egui::Grid::new((table_diff.table, row_change.id)).striped(true).show(ui, |ui| {
+                                    for field_change in &row_change.fields {
+                                        ui.label(&field_change.field);
+                                        ui.label(RichText::new(cell(&field_change.old)).color(REMOVED).strikethrough());
+                                        ui.label("→");
+                                        ui.label(RichText::new(cell(&field_change.new)).color(ADDED));
+                                        ui.end_row();
+                                    }
+                                });
+                            });
+                        }
+                    });
+                }
+            });
+        });
+}
+
+fn row_label(ui: &mut Ui, sign: &str, row: &Value, color: Color32) {
+    ui.label(RichText::new(format!("{} {}", sign, row)).color(color).monospace());
+}
+
+fn cell(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::String(text) => text.clone(),
+        other => other.to_string(),
+    }
+}
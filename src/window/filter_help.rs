@@ -0,0 +1,28 @@
+use egui::{Context, RichText};
+
+use crate::table::filter::SYNTAX;
+
+#[derive(Default, Debug, Clone)]
+pub struct FilterHelpWindow {
+    pub show: bool,
+}
+
+pub fn show(ctx: &Context, window: &mut FilterHelpWindow) {
+    egui::Window::new("🔎 Search syntax")
+        .id(egui::Id::new("filter_help"))
+        .open(&mut window.show)
+        .resizable(false)
+        .collapsible(true)
+        .show(ctx, |ui| {
+            ui.label("Terms are separated by spaces and all of them have to match.");
+            ui.label("Terms about a field a view does not have are ignored there.");
+            ui.separator();
+            egui::Grid::new("filter_help_syntax").striped(true).show(ui, |ui| {
+                for (example, meaning) in SYNTAX {
+                    ui.label(RichText::new(*example).monospace());
+                    ui.label(*meaning);
+                    ui.end_row();
+                }
+            });
+        });
+}
@@ -0,0 +1,88 @@
+use egui::{Color32, Context, RichText};
+use egui_extras::{Column, TableBuilder};
+
+use crate::table::{LoadReport, TableData};
+
+#[derive(Default, Debug, Clone)]
+pub struct DataHealthWindow {
+    pub show: bool,
+}
+
+pub fn show(ctx: &Context, window: &mut DataHealthWindow, data: &TableData, load_report: Option<&LoadReport>) {
+    let integrity = data.integrity();
+    egui::Window::new("🩺 Data health")
+        .id(egui::Id::new("data_health"))
+        .open(&mut window.show)
+        .resizable(true)
+        .collapsible(true)
+        .show(ctx, |ui| {
+            if integrity.is_clean() && load_report.is_none_or(LoadReport::is_clean) {
+                ui.label(RichText::new("✔ no problems found").color(Color32::from_rgb(0, 140, 26)));
+            }
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.collapsing("rows per table", |ui| {
+                    egui::Grid::new("data_health_counts").striped(true).show(ui, |ui| {
+                        for table in data.tables() {
+                            ui.label(table.name());
+                            ui.label(table.len().to_string());
+                            ui.end_row();
+                        }
+                    });
+                });
+                if let Some(report) = load_report.filter(|report| !report.is_clean()) {
+                    ui.heading(format!("rejected rows ({})", report.rejected.len()));
+                    for rejected in &report.rejected {
+                        ui.label(rejected.to_string());
+                    }
+                }
+                if !integrity.dangling.is_empty() {
+                    ui.heading(format!("dangling references ({})", integrity.dangling.len()));
+                    ui.push_id("dangling", |ui| {
+                        TableBuilder::new(ui)
+                            .striped(true)
+                            .column(Column::auto())
+                            .column(Column::auto())
+                            .column(Column::auto())
+                            .column(Column::remainder())
+                            .header(20.0, |mut header| {
+                                for title in ["TABLE", "ROW ID", "COLUMN", "MISSING"] {
+                                    header.col(|ui| {
+                                        ui.strong(title);
+                                    });
+                                }
+                            })
+                            .body(|mut body| {
+                                for dangling in &integrity.dangling {
+                                    body.row(20.0, |mut row| {
+                                        row.col(|ui| {
+                                            ui.label(dangling.table);
+                                        });
+                                        row.col(|ui| {
+                                            ui.label(dangling.row_id.map_or_else(|| "N/A".to_string(), |id| id.to_string()));
+                                        });
+                                        row.col(|ui| {
+                                            ui.label(dangling.column);
+                                        });
+                                        row.col(|ui| {
+                                            ui.label(format!("{:?} #{}", dangling.target, dangling.missing_id));
+                                        });
+                                    });
+                                }
+                            });
+                    });
+                }
+                if !integrity.duplicates.is_empty() {
+                    ui.heading(format!("duplicate ids ({})", integrity.duplicates.len()));
+                    for duplicate in &integrity.duplicates {
+                        ui.label(format!("{} #{} appears {} times", duplicate.table, duplicate.id, duplicate.count));
+                    }
+                }
+                if !integrity.missing_ids.is_empty() {
+                    ui.heading(format!("rows without id ({})", integrity.missing_ids.len()));
+                    for missing in &integrity.missing_ids {
+                        ui.label(format!("{} row {}", missing.table, missing.row));
+                    }
+                }
+            });
+        });
+}
@@ -1,161 +1,178 @@
-use crate::{database::{self, table}, table::query_return::{self, *}};
-#[derive(Debug)]
-pub enum CentralWindowEnum {
-    InProgress,
-    PreOperative
-}
-#[derive(Debug, Default, Clone)]
-pub struct CentralWindow {
-    pub in_progress: InProgressScopeWindow,
-    pub pre_operative: PreOperativeScopeWindow
-}
-impl CentralWindow {
-    pub fn display_last(&self, central_window_enum: CentralWindowEnum) -> Option<&WindowTable> {
-        let mut window_table: Option<&WindowTable> = None;
-        match central_window_enum {
-            CentralWindowEnum::InProgress => {
-                
-            },
-            CentralWindowEnum::PreOperative => {
-                if let Some(current) = &self.pre_operative.tree {
-                    let mut current_child = &current.child;
-                    let current_data = &current.data;
-
-                    if current_child.is_none() {
-                        return Some(&current_data);
-                    }
-
-                    while let Some(ref next) = current_child {
-                        window_table = Some(&next.data); 
-                        current_child = &next.child;
-                    }
-                }
-            },
-        }
-        window_table
-    }
-    fn remove_innermost_child(child: &mut Option<Box<TableTree>>) {
-        if let Some(ref mut child_boxed) = child {
-            if let Some(ref mut next_child) = child_boxed.child {
-                CentralWindow::remove_innermost_child(&mut child_boxed.child);
-            } else {
-                *child = None;
-            }
-        }
-    }
-    
-    pub fn remove_last(&mut self, central_window_enum: CentralWindowEnum) {
-        match central_window_enum {
-            CentralWindowEnum::InProgress => todo!(),
-            CentralWindowEnum::PreOperative => {
-                if let Some(holder) = &mut self.pre_operative.tree {
-                    CentralWindow::remove_innermost_child(&mut holder.child); 
-                }
-            },
-        }
-    }
-    fn push_data_innermost_child(child: &mut Box<TableTree>, window_table: WindowTable) {
-        if let Some(ref mut child) = child.child {
-            if child.child.is_some() {
-                CentralWindow::push_data_innermost_child(child, window_table);
-            } else {
-                child.child = Some(Box::new(TableTree {
-                    data: window_table,
-                    child: None
-                }));
-            }
-        }
-    }
-    pub fn push_last(&mut self, central_window_enum: CentralWindowEnum, window_table: WindowTable) {
-
-        match central_window_enum {
-            CentralWindowEnum::InProgress => {
-            },
-            CentralWindowEnum::PreOperative => {
-                if let Some(holder) = &mut self.pre_operative.tree {
-                    if let Some(ref mut child) = holder.child {
-                        if child.child.is_some() {
-                            CentralWindow::push_data_innermost_child(child, window_table);
-                        } else {
-                            child.child = Some(Box::new(TableTree {
-                                data: window_table,
-                                child: None
-                            }));
-                        }
-                    } else {
-                        holder.child = Some(Box::new(TableTree {
-                            data: window_table,
-                            child: None
-                        }));
-                    }
-                }
-            },
-        }
-    }
-    pub fn initial_tree(&mut self, central_window_enum: CentralWindowEnum, window_table: WindowTable) {
-        match central_window_enum {
-            CentralWindowEnum::InProgress => {
-            },
-            CentralWindowEnum::PreOperative => {
-                self.pre_operative.tree = Some(TableTree {
-                    data: window_table,
-                    child: None
-                });
-            },
-        }
-    }
-    pub fn is_root_state(&self, central_window_enum: CentralWindowEnum) -> bool {
-        match central_window_enum {
-            CentralWindowEnum::InProgress => {
-                false
-            },
-            CentralWindowEnum::PreOperative => {
-                if let Some(tree) = &self.pre_operative.tree {
-                    if tree.child.is_none() {
-                        true
-                    } else {
-                        false
-                    }
-                } else {
-                    false
-                }
-            },
-        }
-    }
-}
-
-pub enum WindowPropertyScope {
-    PreOperative(Option<PreOperativeScope>),
-    InProgress(Option<InProgressScope>)
-}
-
-pub enum PreOperativeScope {
-    PatientData,
-    RoomProperty,
-    ToolReady,
-    CalendarVisualization
-}
-#[derive(Default, Debug, Clone)]
-pub struct PreOperativeScopeWindow {
-    pub show: bool,
-    pub search_input: String,
-    pub enable_scope: bool,
-    pub id_reference: Option<i32>,
-    pub tree: Option<query_return::TableTree>,
-}
-impl PreOperativeScopeWindow {
-    pub fn initial_tree(&mut self) {
-
-    }
-}
-
-pub enum InProgressScope {
-}
-#[derive(Default, Debug, Clone)]
-pub struct InProgressScopeWindow {
-    pub show: bool,
-    pub search_input: String,
-    pub enable_scope: bool,
-    pub id_reference: Option<i32>,
-    pub scope: Option<query_return::WindowTable>
-}
+pub mod conflicts;
+pub mod diff;
+pub mod filter_help;
+pub mod health;
+#[cfg(feature = "sql-mirror")]
+pub mod sql_console;
+pub mod toast;
+
+use chrono::NaiveDateTime;
+
+use crate::{database::table::AlertCategory, table::{build::calendar::CalendarState, query_return::{self, *}, sort::Sort, TableData}};
+use conflicts::ConflictsWindow;
+use diff::SnapshotDiffWindow;
+use filter_help::FilterHelpWindow;
+use health::DataHealthWindow;
+#[cfg(feature = "sql-mirror")]
+use sql_console::SqlConsoleWindow;
+use toast::Toasts;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CentralWindowEnum {
+    InProgress,
+    PreOperative,
+    PostOperative,
+    Recovery,
+    Discharge,
+}
+impl CentralWindowEnum {
+    pub const COUNT: usize = 5;
+}
+/// A change to a row the desk asked for from one of the views.
+#[derive(Debug, Clone)]
+pub enum DeskRequest {
+    /// Move an operation to another time and, when dropped on another lane, another room.
+    Reschedule {
+        op_id: i32,
+        start_time: NaiveDateTime,
+        end_time: NaiveDateTime,
+        room_id: Option<i32>,
+    },
+    /// Mark a tool of an operation as delivered to the room or not.
+    SetOnSite {
+        op_tool_id: i32,
+        on_site: bool,
+    },
+    /// Ask the server to raise an alert about an operation.
+    RaiseAlert {
+        op_id: i32,
+        category: AlertCategory,
+    },
+}
+#[derive(Debug, Default, Clone)]
+pub struct CentralWindow {
+    /// One per board, indexed by `CentralWindowEnum`.
+    scopes: [ScopeWindow; CentralWindowEnum::COUNT],
+    pub data_health: DataHealthWindow,
+    #[cfg(feature = "sql-mirror")]
+    pub sql_console: SqlConsoleWindow,
+    pub snapshot_diff: SnapshotDiffWindow,
+    pub filter_help: FilterHelpWindow,
+    pub conflicts: ConflictsWindow,
+    pub calendar: CalendarState,
+    pub toasts: Toasts,
+    /// Changes asked for by the views, sent by the app after drawing.
+    pub requests: Vec<DeskRequest>,
+}
+impl CentralWindow {
+    pub fn scope(&self, central_window_enum: CentralWindowEnum) -> &ScopeWindow {
+        &self.scopes[central_window_enum as usize]
+    }
+    pub fn scope_mut(&mut self, central_window_enum: CentralWindowEnum) -> &mut ScopeWindow {
+        &mut self.scopes[central_window_enum as usize]
+    }
+    fn tree(&self, central_window_enum: CentralWindowEnum) -> Option<&TableTree> {
+        self.scope(central_window_enum).tree.as_ref()
+    }
+    fn tree_mut(&mut self, central_window_enum: CentralWindowEnum) -> &mut Option<TableTree> {
+        &mut self.scope_mut(central_window_enum).tree
+    }
+    pub fn show_mut(&mut self, central_window_enum: CentralWindowEnum) -> &mut bool {
+        &mut self.scope_mut(central_window_enum).show
+    }
+    pub fn search_input_mut(&mut self, central_window_enum: CentralWindowEnum) -> &mut String {
+        &mut self.scope_mut(central_window_enum).search_input
+    }
+    pub fn search_input(&self, central_window_enum: CentralWindowEnum) -> &str {
+        &self.scope(central_window_enum).search_input
+    }
+    pub fn sort(&self, central_window_enum: CentralWindowEnum) -> Option<&Sort> {
+        self.scope(central_window_enum).sort.as_ref()
+    }
+    pub fn sort_mut(&mut self, central_window_enum: CentralWindowEnum) -> &mut Option<Sort> {
+        &mut self.scope_mut(central_window_enum).sort
+    }
+    pub fn has_tree(&self, central_window_enum: CentralWindowEnum) -> bool {
+        self.tree(central_window_enum).is_some()
+    }
+    pub fn display_last(&self, central_window_enum: CentralWindowEnum) -> Option<&WindowTable> {
+        self.tree(central_window_enum).map(|tree| &tree.last().data)
+    }
+    /// The view the one on screen was drilled into from.
+    pub fn display_parent(&self, central_window_enum: CentralWindowEnum) -> Option<&TableTree> {
+        self.tree(central_window_enum).and_then(|tree| tree.parent_of_last())
+    }
+    pub fn remove_last(&mut self, central_window_enum: CentralWindowEnum) {
+        if let Some(tree) = self.tree_mut(central_window_enum) {
+            tree.pop();
+        }
+    }
+    pub fn push_last(&mut self, central_window_enum: CentralWindowEnum, table_tree: TableTree) {
+        if let Some(tree) = self.tree_mut(central_window_enum) {
+            tree.push(table_tree);
+        }
+    }
+    pub fn initial_tree(&mut self, central_window_enum: CentralWindowEnum, table_tree: TableTree) {
+        *self.tree_mut(central_window_enum) = Some(table_tree);
+    }
+    /// Re-runs the queries of every open view whose tables changed.
+    pub fn refresh(&mut self, table_data: &mut TableData) {
+        for tree in self.scopes.iter_mut().filter_map(|scope| scope.tree.as_mut()) {
+            tree.refresh(table_data);
+        }
+    }
+    /// Titles of the drill-downs open in a window, outermost first.
+    pub fn breadcrumb(&self, central_window_enum: CentralWindowEnum) -> Vec<&'static str> {
+        self.tree(central_window_enum)
+            .map(|tree| tree.path().into_iter().filter_map(PreOperativeScope::of).map(PreOperativeScope::title).collect())
+            .unwrap_or_default()
+    }
+    pub fn is_root_state(&self, central_window_enum: CentralWindowEnum) -> bool {
+        self.tree(central_window_enum).is_some_and(|tree| tree.child.is_none())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PreOperativeScope {
+    PatientData,
+    RoomProperty,
+    ToolReady,
+    CalendarVisualization,
+    StaffTimeline,
+    OperationTeam,
+    ToolDetail,
+}
+impl PreOperativeScope {
+    /// The drill-down a view belongs to, `None` for a board's own table.
+    pub fn of(window_table: &WindowTable) -> Option<PreOperativeScope> {
+        match window_table {
+            WindowTable::PreOperativeToolReady(_) => Some(PreOperativeScope::ToolReady),
+            WindowTable::PatientData(_) => Some(PreOperativeScope::PatientData),
+            WindowTable::RoomProperty(_) => Some(PreOperativeScope::RoomProperty),
+            WindowTable::CalendarVisualization(_) => Some(PreOperativeScope::CalendarVisualization),
+            WindowTable::StaffTimeline(_) => Some(PreOperativeScope::StaffTimeline),
+            WindowTable::OperationTeam(_) => Some(PreOperativeScope::OperationTeam),
+            WindowTable::ToolDetail(_) => Some(PreOperativeScope::ToolDetail),
+            _ => None,
+        }
+    }
+    pub fn title(self) -> &'static str {
+        match self {
+            PreOperativeScope::PatientData => "patient",
+            PreOperativeScope::RoomProperty => "room",
+            PreOperativeScope::ToolReady => "tools",
+            PreOperativeScope::CalendarVisualization => "calendar",
+            PreOperativeScope::StaffTimeline => "staff timeline",
+            PreOperativeScope::OperationTeam => "team",
+            PreOperativeScope::ToolDetail => "tool",
+        }
+    }
+}
+/// State of one operation board: its search, sort and drill-down tree.
+#[derive(Default, Debug, Clone)]
+pub struct ScopeWindow {
+    pub show: bool,
+    pub search_input: String,
+    pub sort: Option<Sort>,
+    pub tree: Option<query_return::TableTree>,
+}
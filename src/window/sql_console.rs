@@ -0,0 +1,88 @@
+use egui::{Color32, Context};
+use egui_extras::{Column, TableBuilder};
+
+use crate::table::mirror::{QueryResult, SqlMirror};
+
+#[derive(Debug, Clone)]
+pub struct SqlConsoleWindow {
+    pub show: bool,
+    pub input: String,
+    pub result: Option<Result<QueryResult, String>>,
+}
+impl Default for SqlConsoleWindow {
+    fn default() -> Self {
+        SqlConsoleWindow {
+            show: false,
+            input: "select label, status, start_time from operation".to_string(),
+            result: None,
+        }
+    }
+}
+
+pub fn show(ctx: &Context, window: &mut SqlConsoleWindow, mirror: &mut Option<SqlMirror>) {
+    let SqlConsoleWindow { show, input, result } = window;
+    egui::Window::new("🗄 SQL console")
+        .id(egui::Id::new("sql_console"))
+        .open(show)
+        .resizable(true)
+        .collapsible(true)
+        .show(ctx, |ui| {
+            let mut enabled = mirror.is_some();
+            if ui.checkbox(&mut enabled, "keep a local SQLite mirror").changed() {
+                if enabled {
+                    match SqlMirror::open() {
+                        Ok(opened) => *mirror = Some(opened),
+                        Err(err) => *result = Some(Err(err.to_string())),
+                    }
+                } else {
+                    *mirror = None;
+                    *result = None;
+                }
+            }
+            let Some(mirror) = mirror else {
+                ui.label("The mirror is off. Turn it on to query the tables with SQL.");
+                return;
+            };
+            ui.add(egui::TextEdit::multiline(input).code_editor().desired_rows(3).desired_width(f32::INFINITY));
+            if ui.button("▶ run").clicked() {
+                *result = Some(mirror.query(input));
+            }
+            match result {
+                Some(Ok(query_result)) => {
+                    ui.label(format!(
+                        "{} rows{}",
+                        query_result.rows.len(),
+                        if query_result.truncated { " (truncated)" } else { "" }
+                    ));
+                    egui::ScrollArea::horizontal().show(ui, |ui| {
+                        let mut table = TableBuilder::new(ui).striped(true);
+                        for _ in &query_result.columns {
+                            table = table.column(Column::auto().resizable(true));
+                        }
+                        table
+                            .header(20.0, |mut header| {
+                                for column in &query_result.columns {
+                                    header.col(|ui| {
+                                        ui.strong(column);
+                                    });
+                                }
+                            })
+                            .body(|body| {
+                                body.rows(20.0, query_result.rows.len(), |mut row| {
+                                    let cells = &query_result.rows[row.index()];
+                                    for cell in cells {
+                                        row.col(|ui| {
+                                            ui.label(cell);
+                                        });
+                                    }
+                                });
+                            });
+                    });
+                },
+                Some(Err(err)) => {
+                    ui.colored_label(Color32::from_rgb(255, 46, 32), err.as_str());
+                },
+                None => {},
+            }
+        });
+}
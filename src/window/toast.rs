@@ -0,0 +1,34 @@
+use chrono::{DateTime, Duration, Utc};
+use egui::{Align2, Color32, Context};
+
+/// How long a toast stays on screen.
+const TOAST_SECONDS: i64 = 6;
+
+/// Short-lived notices in the bottom right corner.
+#[derive(Default, Debug, Clone)]
+pub struct Toasts {
+    entries: Vec<(DateTime<Utc>, String)>,
+}
+impl Toasts {
+    pub fn push(&mut self, message: String) {
+        self.entries.push((Utc::now(), message));
+    }
+}
+
+pub fn show(ctx: &Context, toasts: &mut Toasts) {
+    let deadline = Utc::now() - Duration::seconds(TOAST_SECONDS);
+    toasts.entries.retain(|(shown_at, _)| *shown_at > deadline);
+    if toasts.entries.is_empty() {
+        return;
+    }
+    egui::Area::new(egui::Id::new("toasts"))
+        .anchor(Align2::RIGHT_BOTTOM, egui::vec2(-12.0, -12.0))
+        .show(ctx, |ui| {
+            for (_, message) in &toasts.entries {
+                egui::Frame::popup(ui.style()).show(ui, |ui| {
+                    ui.colored_label(Color32::from_rgb(255, 46, 32), message);
+                });
+            }
+        });
+    ctx.request_repaint_after(std::time::Duration::from_secs(1));
+}
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Deserialize, Debug, Serialize)]
+#[derive(Deserialize, Debug, Serialize, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum TableTarget {
     All,
     Equipment,
@@ -15,12 +15,18 @@ pub enum TableTarget {
     PatientWardRoom,
     PatientWardAssistant,
     OperationStaff,
-    OperationTool
+    OperationTool,
+    Alert,
+    Frontdesk,
+    AlertFrontdesk,
+    AlertStaff
 }
 #[derive(Deserialize, Debug, Serialize)]
 pub enum Operation {
     Initialize,
-    Update
+    Update,
+    Insert,
+    Delete
 }
 #[derive(Deserialize, Debug, Serialize)]
 pub struct ReceiveMessage {
@@ -28,4 +34,10 @@ pub struct ReceiveMessage {
     pub operation: Operation,
     pub status_code: String,
     pub data: String,
-}
\ No newline at end of file
+}
+impl ReceiveMessage {
+    /// Whether the server refused the request this message answers.
+    pub fn is_rejected(&self) -> bool {
+        self.status_code.trim().parse::<u16>().is_ok_and(|code| code >= 400)
+    }
+}